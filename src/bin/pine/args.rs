@@ -1,11 +1,21 @@
 use clap::{Parser, Subcommand, ValueEnum};
 use rusty_pine::analyze::{DBType as AnalyzeDBType, DatabaseName, ServerParams};
 use rusty_pine::context::Context;
+use rusty_pine::InternalError;
+use std::path::PathBuf;
+use std::{env, fs};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 #[command(propagate_version = true)]
 pub struct Args {
+    /// Use this context instead of the current one, without switching to it.
+    #[arg(long, global = true)]
+    pub context: Option<String>,
+    /// Where to keep contexts and analyzed databases. Defaults to $PINE_CACHE_DIR,
+    /// $XDG_CACHE_HOME/rusty-pine or ~/.cache/rusty-pine.
+    #[arg(long, global = true)]
+    pub cache_dir: Option<PathBuf>,
     #[command(subcommand)]
     pub command: Command,
 }
@@ -20,35 +30,65 @@ pub enum Command {
     UseContext { name: String },
     /// List available contexts.
     ListContexts,
+    /// Deletes a context. The analyzed database structure is kept, other contexts might use it.
+    DeleteContext { name: String },
+    /// Renames a context.
+    RenameContext { name: String, new_name: String },
+    /// Shows the connection details of a context, and when it was analyzed. Defaults to the
+    /// current context.
+    ShowContext { name: Option<String> },
+    /// Changes the connection details of a context. Only the given flags are changed.
+    EditContext(EditContextParams),
     /// Analyzes the database of the current context, updating the known structure used when
     /// analyzing pines.
-    Analyze,
+    Analyze(AnalyzeParams),
+    /// Manages relations the database does not know about, for the current context.
+    ///
+    /// Relations work like foreign keys: they show up in neighbor listings and are used for
+    /// auto-joins.
+    Relation {
+        #[command(subcommand)]
+        command: RelationCommand,
+    },
+    /// Saves the analyzed structure of the current context to a file, for schema-diff.
+    Snapshot { file: PathBuf },
+    /// Compares the analyzed structure of two contexts or snapshot files: tables, columns,
+    /// primary keys and foreign keys.
+    SchemaDiff(SchemaDiffParams),
+    /// Prints the tables and foreign keys around a table, or of the whole default database, as
+    /// a Graphviz or Mermaid diagram.
+    Graph(GraphParams),
     /// Runs a pine server that can be used with https://try.pine-lang.org/
     PineServer,
     /// Translates a single pine to SQL using the current context.
     Translate { input: String },
 }
 
+/// The connection can be described with either a URL or the separate flags. Without either,
+/// DATABASE_URL is used, from the environment or from a .env file in the current directory.
 #[derive(clap::Args, Debug)]
 pub struct ContextParams {
     /// You can reuse your context by referencing this name
     name: String,
 
+    /// Connection URL, e.g. postgres://user@host:5432/db?schema=public or mariadb://user@host/db
+    #[arg(long, conflicts_with_all = ["db_type", "hostname_or_ip", "port", "username", "database"])]
+    url: Option<String>,
     ///. Database type: PostgresSQL, MariaDB.
     #[arg(long = "type")]
-    db_type: DBType,
+    db_type: Option<DBType>,
     /// Hostname or ip address of the MySQL server (without the port number)
     #[arg(value_enum, long = "host")]
-    hostname_or_ip: String,
+    hostname_or_ip: Option<String>,
     /// Port number of the database server
     #[arg(short, long)]
-    port: u16,
+    port: Option<u16>,
     /// Username
     #[arg(short, long)]
-    username: String,
+    username: Option<String>,
     /// Database. Will be used for the database to scan or the default database for MariaDB.
     #[arg(short, long)]
-    database: String,
+    database: Option<String>,
     /// When using Postgres, this is the schema used when the user does not specify one.
     #[arg(short = 's', long)]
     default_schema: Option<String>,
@@ -57,26 +97,212 @@ pub struct ContextParams {
     pub use_it: bool,
 }
 
+#[derive(clap::Args, Debug)]
+pub struct EditContextParams {
+    pub name: String,
+
+    /// Connection URL, replaces all connection details.
+    #[arg(long, conflicts_with_all = ["db_type", "hostname_or_ip", "port", "username", "database"])]
+    url: Option<String>,
+    ///. Database type: PostgresSQL, MariaDB.
+    #[arg(long = "type")]
+    db_type: Option<DBType>,
+    /// Hostname or ip address of the MySQL server (without the port number)
+    #[arg(long = "host")]
+    hostname_or_ip: Option<String>,
+    /// Port number of the database server
+    #[arg(short, long)]
+    port: Option<u16>,
+    /// Username
+    #[arg(short, long)]
+    username: Option<String>,
+    /// Database. Will be used for the database to scan or the default database for MariaDB.
+    #[arg(short, long)]
+    database: Option<String>,
+    /// When using Postgres, this is the schema used when the user does not specify one.
+    #[arg(short = 's', long)]
+    default_schema: Option<String>,
+}
+
+impl EditContextParams {
+    pub fn apply(self, params: &mut ServerParams) -> Result<(), rusty_pine::Error> {
+        if let Some(url) = self.url {
+            *params = ServerParams::from_url(&url)?;
+        }
+        if let Some(db_type) = self.db_type {
+            params.db_type = db_type.into();
+        }
+        if let Some(hostname) = self.hostname_or_ip {
+            params.hostname = hostname;
+        }
+        if let Some(port) = self.port {
+            params.port = port;
+        }
+        if let Some(user) = self.username {
+            params.user = user;
+        }
+        if let Some(database) = self.database {
+            params.database = database.into();
+        }
+        if let Some(default_schema) = self.default_schema {
+            params.default_schema = Some(DatabaseName(default_schema));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct GraphParams {
+    /// Start from this table, written as [database.]table. Shows the whole default database
+    /// when missing.
+    pub table: Option<String>,
+    /// How many foreign keys away from the table to go.
+    #[arg(long, default_value_t = 1, requires = "table")]
+    pub depth: usize,
+    #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+    pub format: GraphFormat,
+}
+
+#[derive(Debug, ValueEnum, Clone, Copy)]
+pub enum GraphFormat {
+    /// Graphviz, render with `dot -Tsvg`.
+    Dot,
+    /// Mermaid entity relationship diagram, rendered by GitHub and GitLab.
+    Mermaid,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SchemaDiffParams {
+    /// Context name or snapshot file.
+    pub from: String,
+    /// Context name or snapshot file.
+    pub to: String,
+    /// Exit with 1 when there are differences. Errors exit with 2.
+    #[arg(long)]
+    pub exit_code: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RelationCommand {
+    /// Adds a relation, e.g. `pine relation add orders.userId users.id`.
+    ///
+    /// Use + for multiple columns (orders.a+b) and prefix with the database or schema to
+    /// reference tables outside the default one (billing.invoices.userId).
+    Add { from: String, to: String },
+    /// Lists all relations.
+    List,
+    /// Removes a relation, using the number shown by `pine relation list`.
+    Remove { number: usize },
+}
+
+/// Without any flags, analyze asks for the password and the databases interactively. The flags
+/// allow running it from scripts, cron jobs or CI.
+#[derive(clap::Args, Debug)]
+pub struct AnalyzeParams {
+    /// Guess foreign keys from column names like userId or user_id. Useful for databases that
+    /// don't declare their foreign keys. Refreshing keeps doing it if the last analysis did.
+    #[arg(long)]
+    pub infer_foreign_keys: bool,
+    /// Database (MariaDB) or schema (Postgres) to analyze, can be repeated. Accepts glob
+    /// patterns like "app_*".
+    #[arg(long = "database", conflicts_with_all = ["all", "refresh"])]
+    pub databases: Vec<String>,
+    /// Analyze all databases (MariaDB) or schemas (Postgres).
+    #[arg(long, conflicts_with = "refresh")]
+    pub all: bool,
+    /// Analyze the databases analyzed last time again, and show what changed since then.
+    #[arg(long)]
+    pub refresh: bool,
+    /// Read the password from this environment variable.
+    #[arg(long, group = "password")]
+    pub password_env: Option<String>,
+    /// Read the password from the first line of this file.
+    #[arg(long, group = "password")]
+    pub password_file: Option<PathBuf>,
+    /// Read the password from the first line of stdin.
+    #[arg(long, group = "password")]
+    pub password_stdin: bool,
+}
+
 #[derive(Debug, ValueEnum, Clone)]
 pub enum DBType {
     MariaDB,
     PostgresSQL,
 }
 
-impl From<ContextParams> for Context {
-    fn from(value: ContextParams) -> Self {
-        Context {
-            name: value.name.into(),
-            server_params: ServerParams {
-                db_type: value.db_type.into(),
-                hostname: value.hostname_or_ip,
-                port: value.port,
-                user: value.username,
-                database: value.database.into(),
-                default_schema: value.default_schema.map(DatabaseName),
+impl TryFrom<ContextParams> for Context {
+    type Error = rusty_pine::Error;
+
+    fn try_from(value: ContextParams) -> Result<Self, Self::Error> {
+        let url = match value.url {
+            Some(url) => Some(url),
+            None if value.db_type.is_none()
+                && value.hostname_or_ip.is_none()
+                && value.port.is_none()
+                && value.username.is_none()
+                && value.database.is_none() =>
+            {
+                Some(database_url()?)
+            }
+            None => None,
+        };
+
+        let mut server_params = match url {
+            Some(url) => ServerParams::from_url(&url)?,
+            None => ServerParams {
+                db_type: required(value.db_type, "--type")?.into(),
+                hostname: required(value.hostname_or_ip, "--host")?,
+                port: required(value.port, "--port")?,
+                user: required(value.username, "--username")?,
+                database: required(value.database, "--database")?.into(),
+                default_schema: None,
             },
+        };
+
+        // The flag wins over the schema in the URL.
+        if let Some(default_schema) = value.default_schema {
+            server_params.default_schema = Some(DatabaseName(default_schema));
         }
+
+        Ok(Context {
+            name: value.name.into(),
+            server_params,
+        })
+    }
+}
+
+fn required<T>(value: Option<T>, flag: &str) -> Result<T, InternalError> {
+    value.ok_or_else(|| InternalError(format!("{flag} is required when not using --url")))
+}
+
+/// Existing projects usually have a DATABASE_URL, either exported or in their .env file.
+fn database_url() -> Result<String, InternalError> {
+    if let Ok(url) = env::var("DATABASE_URL") {
+        return Ok(url);
     }
+
+    let from_dotenv = fs::read_to_string(".env").ok().and_then(|content| {
+        content.lines().find_map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+
+            (key.trim() == "DATABASE_URL").then(|| {
+                value
+                    .trim()
+                    .trim_matches(|c| c == '"' || c == '\'')
+                    .to_string()
+            })
+        })
+    });
+
+    from_dotenv.ok_or_else(|| {
+        InternalError(
+            "Use --url or the connection flags, or set DATABASE_URL. See --help for more info"
+                .to_string(),
+        )
+    })
 }
 
 impl From<DBType> for AnalyzeDBType {
@@ -1,15 +1,23 @@
+use crate::args::AnalyzeParams;
+use crate::commands::time_ago;
 use colored::Colorize;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{MultiSelect, Password};
+use regex::Regex;
 use rusty_pine::analyze::{
-    mariadb, postgres, Analyzer, DBType, Database, DatabaseName, SchemaObjectName, Server, Table,
+    diff_databases, infer_foreign_keys, mariadb, postgres, Analyzer, DBType, Database,
+    DatabaseName, SchemaObjectName, Server, Table,
 };
 use rusty_pine::context::{Context, ContextName};
+use rusty_pine::credentials::find_password;
 use rusty_pine::{cache, Error, InternalError};
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::time::SystemTime;
 use tokio::runtime::Builder;
 
-pub fn analyze() -> Result<(), Error> {
+pub fn analyze(params: AnalyzeParams) -> Result<(), Error> {
     // we need tokio here because sqlx is exclusively async
     let tokio = Builder::new_current_thread()
         .enable_io()
@@ -17,112 +25,242 @@ pub fn analyze() -> Result<(), Error> {
         .build()
         .expect("Cannot build tokio runtime");
 
-    tokio.block_on(async { run_analyze().await })
+    tokio.block_on(async { run_analyze(params).await })
 }
 
-async fn run_analyze() -> Result<(), Error> {
+async fn run_analyze(params: AnalyzeParams) -> Result<(), Error> {
     let current_context = ContextName::current()?;
     let context: Context = cache::read(&current_context)?;
 
-    let password = ask_for_password(&context)?;
+    println!("Using context {}", context.name.to_string().bold().green());
+    let password = read_password(&context, &params)?;
 
     let db_connection: Box<dyn Analyzer> = match context.server_params.db_type {
-        DBType::PostgresSQL => Box::new(
-            postgres(context.server_params.clone(), &password)
-                .await
-                .expect("Could not connect to the PostgresSQL instance"),
-        ),
-        DBType::MariaDB => Box::new(
-            mariadb(context.server_params.clone(), &password)
-                .await
-                .expect("Could not connect to the MariaDB instance"),
-        ),
+        DBType::PostgresSQL => Box::new(postgres(context.server_params.clone(), &password).await?),
+        DBType::MariaDB => Box::new(mariadb(context.server_params.clone(), &password).await?),
     };
 
     let databases = db_connection.list_databases().await?;
+    let previous = match params.refresh {
+        true => Some(cache::read_server(&context.server_params, None)?),
+        false => None,
+    };
 
-    println!(
-        "Use arrow keys (⬆⬇) to navigate, {} to select, and {} to confirm.",
-        "<space>".bold(),
-        "<enter>".bold(),
-    );
-
-    let selection = MultiSelect::with_theme(&ColorfulTheme::default())
-        .with_prompt("Select databases")
-        .items(&databases)
-        .interact()?;
-
-    // You'd normally expect to see something like selection.map(|i| databases.get(i)).
-    // The approach tries to move data into this closure -------/********************.
-    // That is not possible. So we take all the database names, and iterate over that instead.
-    let selected_databases: Vec<_> = databases
-        .into_iter()
-        .enumerate()
-        .filter(|(index, _)| selection.contains(index))
-        .map(|(_, item)| item)
-        .collect();
+    let selected_databases = if let Some(previous) = &previous {
+        // Databases dropped since then will show up in the changes.
+        databases
+            .into_iter()
+            .filter(|db| {
+                previous
+                    .databases()
+                    .contains_key(&DatabaseName::new(db.clone()))
+            })
+            .collect()
+    } else if params.all {
+        databases
+    } else if !params.databases.is_empty() {
+        filter_databases(databases, &params.databases)?
+    } else {
+        select_databases(databases)?
+    };
 
+    // Otherwise a refresh would report every inferred key as dropped.
+    let infer_foreign_keys = params.infer_foreign_keys
+        || previous
+            .as_ref()
+            .is_some_and(|previous| previous.infers_foreign_keys);
     let mut databases = HashMap::new();
 
     for db_name in selected_databases {
-        let database = analyze_db(db_connection.as_ref(), db_name.clone()).await?;
+        let database =
+            analyze_db(db_connection.as_ref(), db_name.clone(), infer_foreign_keys).await?;
         let db_name = DatabaseName(db_name.as_str().to_string());
 
         databases.insert(db_name, database);
     }
 
-    let server = Server {
-        params: context.server_params,
-        databases,
-    };
+    let mut server = Server::new(context.server_params, databases);
+    server.analyzed_at = Some(SystemTime::now());
+    server.infers_foreign_keys = infer_foreign_keys;
 
-    cache::write(&server)?;
+    cache::write_server(&server)?;
 
     println!("Database analyzed and cached");
+
+    if let Some(previous) = previous {
+        print_changes(&previous, &server);
+    }
+
     Ok(())
 }
 
+fn print_changes(previous: &Server, server: &Server) {
+    let changes = diff_databases(previous.databases(), server.databases());
+    let since = match previous.analyzed_at {
+        Some(analyzed_at) => format!(" since the last analysis ({})", time_ago(analyzed_at)),
+        None => " since the last analysis".to_string(),
+    };
+
+    if changes.is_empty() {
+        println!("No changes{since}");
+        return;
+    }
+
+    println!("{} changes{since}:", changes.len());
+    for change in changes {
+        let line = change.to_string();
+
+        match line.starts_with('+') {
+            true => println!("  {}", line.green()),
+            false => println!("  {}", line.red()),
+        }
+    }
+}
+
 async fn analyze_db(
     connection: &dyn Analyzer,
     db_name: SchemaObjectName,
+    infer_fks: bool,
 ) -> Result<Database, Error> {
-    let table_names = connection.list_tables(&db_name).await?;
+    let tables_and_kinds = connection.list_tables(&db_name).await?;
     let mut all_columns = connection.table_columns(&db_name).await?;
     let mut all_fks = connection.table_foreign_keys(&db_name).await?;
     let mut all_pks = connection.table_primary_keys(&db_name).await?;
+    let mut all_indexes = connection.table_indexes(&db_name).await?;
 
     let mut tables = HashMap::new();
-    for table_name in table_names {
+    for (table_name, kind) in tables_and_kinds {
         let columns = all_columns.remove(&table_name).unwrap_or_default();
         let foreign_keys = all_fks.remove(&table_name).unwrap_or_default();
-        let primary_key = all_pks.remove(&table_name).ok_or(InternalError(
-            "Encountered tables without primary keys".to_string(),
-        ))?;
+        // Views and some log tables don't have primary keys, that's fine.
+        let primary_key = all_pks.remove(&table_name);
+        let indexes = all_indexes.remove(&table_name).unwrap_or_default();
 
         tables.insert(
             table_name.clone(),
             Table {
                 name: table_name,
+                kind,
                 columns,
                 foreign_keys,
                 primary_key,
+                indexes,
             },
         );
     }
 
+    if infer_fks {
+        let inferred = infer_foreign_keys(&mut tables);
+        println!("Inferred {inferred} foreign keys in {}", db_name.as_str());
+    }
+
     Ok(Database {
         name: DatabaseName::new(db_name),
         tables: tables.into(),
     })
 }
 
+fn select_databases(databases: Vec<SchemaObjectName>) -> Result<Vec<SchemaObjectName>, Error> {
+    println!(
+        "Use arrow keys (⬆⬇) to navigate, {} to select, and {} to confirm.",
+        "<space>".bold(),
+        "<enter>".bold(),
+    );
+
+    let selection = MultiSelect::with_theme(&ColorfulTheme::default())
+        .with_prompt("Select databases")
+        .items(&databases)
+        .interact()?;
+
+    // You'd normally expect to see something like selection.map(|i| databases.get(i)).
+    // The approach tries to move data into this closure -------/********************.
+    // That is not possible. So we take all the database names, and iterate over that instead.
+    Ok(databases
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| selection.contains(index))
+        .map(|(_, item)| item)
+        .collect())
+}
+
+/// Every pattern has to match at least one database. A typo in a cron job should fail loudly
+/// instead of silently analyzing nothing.
+fn filter_databases<T: AsRef<str>>(
+    databases: Vec<T>,
+    patterns: &[String],
+) -> Result<Vec<T>, Error> {
+    let patterns = patterns
+        .iter()
+        .map(|pattern| Ok((pattern, glob_to_regex(pattern)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for (pattern, regex) in &patterns {
+        if !databases.iter().any(|db| regex.is_match(db.as_ref())) {
+            Err(InternalError(format!(
+                "No database or schema matches \"{pattern}\""
+            )))?;
+        }
+    }
+
+    Ok(databases
+        .into_iter()
+        .filter(|db| {
+            patterns
+                .iter()
+                .any(|(_, regex)| regex.is_match(db.as_ref()))
+        })
+        .collect())
+}
+
+/// Supports * and ? wildcards, everything else is matched literally.
+fn glob_to_regex(pattern: &str) -> Result<Regex, Error> {
+    let regex = regex::escape(pattern)
+        .replace(r"\*", ".*")
+        .replace(r"\?", ".");
+
+    Regex::new(&format!("^{regex}$"))
+        .map_err(|error| InternalError(format!("Invalid pattern \"{pattern}\": {error}")).into())
+}
+
+fn read_password(context: &Context, params: &AnalyzeParams) -> Result<String, Error> {
+    if let Some(variable) = &params.password_env {
+        return std::env::var(variable).map_err(|error| {
+            InternalError(format!("Can't read the password from ${variable}: {error}")).into()
+        });
+    }
+
+    if let Some(file) = &params.password_file {
+        let content = fs::read_to_string(file)?;
+
+        return Ok(first_line(&content).to_string());
+    }
+
+    if params.password_stdin {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+
+        return Ok(first_line(&line).to_string());
+    }
+
+    if let Some(found) = find_password(&context.server_params) {
+        println!("Using the password from {}", found.file.display());
+
+        return Ok(found.password);
+    }
+
+    ask_for_password(context)
+}
+
+fn first_line(content: &str) -> &str {
+    content.lines().next().unwrap_or_default()
+}
+
 /// Ask the user for a password.
 ///
 /// I don't want to store passwords, it's too complicated to do safely.
 fn ask_for_password(context: &Context) -> Result<String, Error> {
     // the password is the only part that we have to read interactively
     // we use the dialoguer library for this bec
-    println!("Using context {}", context.name.to_string().bold().green());
     println!(
         "Please provide the password for {}",
         context.server_params.to_string().bold().green()
@@ -131,3 +269,36 @@ fn ask_for_password(context: &Context) -> Result<String, Error> {
         .with_prompt("Password: ")
         .interact()?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_to_regex() {
+        let regex = glob_to_regex("shop_?.*").unwrap();
+
+        assert!(regex.is_match("shop_1.orders"));
+        assert!(!regex.is_match("shop_12.orders"));
+        assert!(!regex.is_match("shop_1_orders"));
+        assert!(!regex.is_match("old_shop_1.orders"));
+    }
+
+    #[test]
+    fn test_filter_databases() {
+        let databases = vec!["shop", "shop_test", "billing"];
+        let patterns = |patterns: &[&str]| -> Vec<String> {
+            patterns.iter().map(|pattern| pattern.to_string()).collect()
+        };
+
+        let filtered = filter_databases(databases.clone(), &patterns(&["shop*"])).unwrap();
+        assert_eq!(filtered, ["shop", "shop_test"]);
+
+        let filtered =
+            filter_databases(databases.clone(), &patterns(&["billing", "shop"])).unwrap();
+        assert_eq!(filtered, ["shop", "billing"]);
+
+        let error = filter_databases(databases, &patterns(&["shop", "reports"])).unwrap_err();
+        assert!(error.to_string().contains("\"reports\""), "{error}");
+    }
+}
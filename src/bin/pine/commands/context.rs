@@ -0,0 +1,207 @@
+use crate::args::{ContextParams, EditContextParams};
+use crate::commands::time_ago;
+use colored::Colorize;
+use rusty_pine::analyze::DBType;
+use rusty_pine::cache::{Cacheable, ServerIndex};
+use rusty_pine::context::{Context, ContextName};
+use rusty_pine::relations::Relations;
+use rusty_pine::{cache, InternalError};
+
+pub fn create_context(params: ContextParams) -> Result<(), rusty_pine::Error> {
+    let use_it = params.use_it;
+    let new_context: Context = params.try_into()?;
+
+    validate_new_context(&new_context)?;
+
+    cache::write(&new_context)?;
+
+    println!("Create new context \x1b[1m{}\x1b[0m.", new_context.name);
+
+    if use_it {
+        use_context(new_context.name.into())?;
+    } else {
+        println!(
+            "Switch to it by running \x1b[1mpine use-context {}\x1b[0m.",
+            new_context.name
+        );
+    }
+
+    Ok(())
+}
+
+fn validate_new_context(context: &Context) -> Result<(), rusty_pine::Error> {
+    if context.server_params.db_type == DBType::PostgresSQL
+        && context.server_params.default_schema.is_none()
+    {
+        Err(InternalError(
+            "You must specify the default schema when using postgres. \
+                See --help for more info"
+                .to_string(),
+        ))?;
+    }
+
+    Ok(())
+}
+
+pub fn use_context(name: String) -> Result<(), rusty_pine::Error> {
+    let context_name: ContextName = name.into();
+
+    cache::write(&context_name)?;
+
+    println!("Switched to context \x1b[1m{}\x1b[0m.", context_name);
+
+    Ok(())
+}
+
+/// The marker shows what `pine use-context` saved. `--context` or a `.pine.json` can pick another
+/// context for this command, that one is labeled.
+pub fn list_contexts() -> Result<(), rusty_pine::Error> {
+    let persisted_context = ContextName::persisted().ok();
+    let current_context = ContextName::current().ok();
+    let known_contexts: Vec<Context> = cache::read_all()?;
+
+    println!("Available contexts:");
+    for context in &known_contexts {
+        let is_current = current_context.as_ref() == Some(&context.name);
+        let is_persisted = persisted_context.as_ref() == Some(&context.name);
+
+        println!(
+            "{}{}: {} ({}){}",
+            if is_persisted {
+                " * ".bold()
+            } else {
+                "   ".into()
+            },
+            context.name.to_string().bold(),
+            context.server_params.hostname,
+            context.server_params.database,
+            if is_current && !is_persisted {
+                " <- used here, from --context or .pine.json".yellow()
+            } else {
+                "".into()
+            },
+        )
+    }
+
+    Ok(())
+}
+
+pub fn delete_context(name: String) -> Result<(), rusty_pine::Error> {
+    let context: Context = read_context(name)?;
+
+    cache::delete::<Context, _>(&context.name)?;
+    delete_relations(&context.name)?;
+
+    // We don't want to leave the current context pointing to nothing.
+    if ContextName::persisted().is_ok_and(|current| current == context.name) {
+        cache::delete::<ContextName, _>(&context.name.cache_key())?;
+        println!(
+            "Deleted context \x1b[1m{}\x1b[0m, no context is selected now.",
+            context.name
+        );
+    } else {
+        println!("Deleted context \x1b[1m{}\x1b[0m.", context.name);
+    }
+
+    Ok(())
+}
+
+pub fn rename_context(name: String, new_name: String) -> Result<(), rusty_pine::Error> {
+    let mut context: Context = read_context(name)?;
+    let old_name = context.name.clone();
+    let new_name: ContextName = new_name.into();
+
+    if cache::read::<Context, _>(&new_name).is_ok() {
+        Err(InternalError(format!("Context {new_name} already exists")))?;
+    }
+
+    context.name = new_name.clone();
+    cache::write(&context)?;
+
+    let mut relations = Relations::read(&old_name)?;
+    if !relations.relations.is_empty() {
+        relations.context = new_name.clone();
+        cache::write(&relations)?;
+    }
+    delete_relations(&old_name)?;
+    cache::delete::<Context, _>(&old_name)?;
+
+    if ContextName::persisted().is_ok_and(|current| current == old_name) {
+        cache::write(&new_name)?;
+    }
+
+    println!("Renamed context \x1b[1m{old_name}\x1b[0m to \x1b[1m{new_name}\x1b[0m.");
+
+    Ok(())
+}
+
+pub fn show_context(name: Option<String>) -> Result<(), rusty_pine::Error> {
+    let name = match name {
+        Some(name) => name.into(),
+        None => ContextName::current().map_err(|_| {
+            InternalError("No context selected, run `pine use-context <name>` first".to_string())
+        })?,
+    };
+    let context: Context = cache::read(&name)?;
+    let params = &context.server_params;
+
+    println!("Context {}", context.name.to_string().bold());
+    println!("  type:            {:?}", params.db_type);
+    println!("  server:          {params}");
+    println!("  database:        {}", params.database.0);
+    if let Some(default_schema) = &params.default_schema {
+        println!("  default schema:  {}", default_schema.0);
+    }
+
+    match context.server() {
+        Ok(server) => {
+            // Older caches don't know when they were analyzed, the file is a good approximation.
+            let analyzed_at = server
+                .analyzed_at
+                .map_or_else(|| cache::last_written::<ServerIndex, _>(params), Ok)?;
+            let tables: usize = server
+                .databases()
+                .values()
+                .map(|database| database.tables.iter().count())
+                .sum();
+
+            println!(
+                "  analyzed:        {} ({tables} tables)",
+                time_ago(analyzed_at)
+            );
+        }
+        Err(_) => println!("  analyzed:        never, run `pine analyze`"),
+    }
+
+    Ok(())
+}
+
+pub fn edit_context(params: EditContextParams) -> Result<(), rusty_pine::Error> {
+    let mut context: Context = read_context(params.name.clone())?;
+
+    params.apply(&mut context.server_params)?;
+    validate_new_context(&context)?;
+
+    cache::write(&context)?;
+
+    println!(
+        "Updated context \x1b[1m{}\x1b[0m, run \x1b[1mpine analyze\x1b[0m if it points to a different server now.",
+        context.name
+    );
+
+    Ok(())
+}
+
+fn read_context(name: String) -> Result<Context, rusty_pine::Error> {
+    let name: ContextName = name.into();
+
+    cache::read(&name).map_err(|_| InternalError(format!("Context {name} does not exist")).into())
+}
+
+fn delete_relations(name: &ContextName) -> Result<(), rusty_pine::Error> {
+    if !Relations::read(name)?.relations.is_empty() {
+        cache::delete::<Relations, _>(name)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,308 @@
+use crate::args::{GraphFormat, GraphParams};
+use rusty_pine::analyze::{DatabaseName, ForeignKey, ForeignKeyKind, Server, TableName};
+use rusty_pine::context::{Context, ContextName};
+use rusty_pine::{cache, Error, InternalError};
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+type Node = (DatabaseName, TableName);
+
+pub fn graph(params: GraphParams) -> Result<(), Error> {
+    let context: Context = cache::read(&ContextName::current()?)?;
+    let server = context.server()?;
+    let default_database = server
+        .params
+        .default_database()
+        .cloned()
+        .ok_or_else(|| InternalError("The context has no default schema".to_string()))?;
+
+    let mut nodes: Vec<Node> = match &params.table {
+        Some(table) => {
+            let start = parse_table(table, &default_database);
+            let database = server.databases().get(&start.0);
+
+            if database
+                .and_then(|database| database.tables.get(&start.1))
+                .is_none()
+            {
+                Err(InternalError(format!("Table {table} not found")))?;
+            }
+
+            server
+                .foreign_key_graph()
+                .neighborhood(start, params.depth)
+                .into_iter()
+                .collect()
+        }
+        None => server
+            .databases()
+            .get(&default_database)
+            .into_iter()
+            .flat_map(|database| database.tables.iter())
+            .map(|(name, _)| (default_database.clone(), name.clone()))
+            .collect(),
+    };
+    nodes.sort_by_key(|(database, table)| (database.0.clone(), table.to_string()));
+
+    let edges = edges(&server, &nodes);
+    let name = |(database, table): &Node| match database == &default_database {
+        true => table.to_string(),
+        false => format!("{}.{table}", database.0),
+    };
+
+    match params.format {
+        GraphFormat::Dot => write_dot(&nodes, &edges, name, &mut io::stdout())?,
+        GraphFormat::Mermaid => write_mermaid(&nodes, &edges, name, &mut io::stdout())?,
+    }
+
+    Ok(())
+}
+
+fn parse_table(input: &str, default_database: &DatabaseName) -> Node {
+    match input.split_once('.') {
+        Some((database, table)) => (DatabaseName(database.to_string()), table.into()),
+        None => (default_database.clone(), input.into()),
+    }
+}
+
+/// Only keys between tables that are part of the graph.
+fn edges<'a>(server: &'a Server, nodes: &[Node]) -> Vec<(Node, &'a ForeignKey)> {
+    let graph = server.foreign_key_graph();
+    let included: HashSet<&Node> = nodes.iter().collect();
+
+    nodes
+        .iter()
+        .flat_map(|(database, table)| {
+            graph
+                .outgoing(database, table)
+                .iter()
+                .map(move |fk| ((database.clone(), table.clone()), fk))
+        })
+        .filter(|((database, _), fk)| {
+            included.contains(&(fk.to.database_or(database).clone(), fk.to.table.clone()))
+        })
+        .collect()
+}
+
+fn target(from_database: &DatabaseName, fk: &ForeignKey) -> Node {
+    (
+        fk.to.database_or(from_database).clone(),
+        fk.to.table.clone(),
+    )
+}
+
+fn columns(fk: &ForeignKey) -> String {
+    fk.from
+        .key
+        .columns
+        .iter()
+        .map(|column| column.0.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Keys the database doesn't know about are dashed.
+fn write_dot(
+    nodes: &[Node],
+    edges: &[(Node, &ForeignKey)],
+    name: impl Fn(&Node) -> String,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "digraph {{")?;
+    writeln!(out, "  node [shape=box];")?;
+
+    for node in nodes {
+        writeln!(out, "  {};", dot_string(&name(node)))?;
+    }
+
+    for (from, fk) in edges {
+        let style = match fk.kind {
+            ForeignKeyKind::Declared => "",
+            ForeignKeyKind::Inferred | ForeignKeyKind::Virtual => ", style=dashed",
+        };
+
+        writeln!(
+            out,
+            "  {} -> {} [label={}{style}];",
+            dot_string(&name(from)),
+            dot_string(&name(&target(&from.0, fk))),
+            dot_string(&columns(fk))
+        )?;
+    }
+
+    writeln!(out, "}}")
+}
+
+/// A DOT quoted string. Inside quotes only `"` needs escaping, but a backslash would start an
+/// escape sequence like `\n` in labels, so it's escaped too.
+fn dot_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+/// Keys the database doesn't know about are drawn as non-identifying (dotted) relationships.
+fn write_mermaid(
+    nodes: &[Node],
+    edges: &[(Node, &ForeignKey)],
+    name: impl Fn(&Node) -> String,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    writeln!(out, "erDiagram")?;
+
+    // Tables with keys show up through their relationships.
+    for node in nodes {
+        let has_edges = edges
+            .iter()
+            .any(|(from, fk)| from == node || &target(&from.0, fk) == node);
+
+        if !has_edges {
+            writeln!(out, "  {}", mermaid_name(&name(node)))?;
+        }
+    }
+
+    for (from, fk) in edges {
+        let line = match fk.kind {
+            ForeignKeyKind::Declared => "--",
+            ForeignKeyKind::Inferred | ForeignKeyKind::Virtual => "..",
+        };
+
+        writeln!(
+            out,
+            "  {} }}o{line}|| {} : {}",
+            mermaid_name(&name(from)),
+            mermaid_name(&name(&target(&from.0, fk))),
+            mermaid_string(&columns(fk))
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Mermaid only needs quotes for names like `billing.invoices`.
+fn mermaid_name(name: &str) -> String {
+    match name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        true => name.to_string(),
+        false => mermaid_string(name),
+    }
+}
+
+/// Mermaid strings can't contain `"` at all, it has to be written as an entity code. `#` starts
+/// entity codes, so it's written as one too.
+fn mermaid_string(value: &str) -> String {
+    let escaped = value.replace('#', "#35;").replace('"', "#quot;");
+
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusty_pine::analyze::{Key, KeyReference};
+
+    fn key(database: Option<&str>, table: &str, column: &str) -> KeyReference {
+        KeyReference {
+            database: database.map(|database| database.into()),
+            table: table.into(),
+            key: Key {
+                columns: vec![column.into()],
+            },
+        }
+    }
+
+    fn node(database: &str, table: &str) -> Node {
+        (database.into(), table.into())
+    }
+
+    fn name((database, table): &Node) -> String {
+        match database.0.as_str() {
+            "shop" => table.to_string(),
+            _ => format!("{}.{table}", database.0),
+        }
+    }
+
+    /// orders -> users (inferred) and orders -> billing.invoices (declared), carts on its own.
+    fn example() -> (Vec<Node>, Vec<ForeignKey>) {
+        let nodes = vec![
+            node("billing", "invoices"),
+            node("shop", "carts"),
+            node("shop", "orders"),
+            node("shop", "users"),
+        ];
+        let keys = vec![
+            ForeignKey {
+                from: key(None, "orders", "invoiceId"),
+                to: key(Some("billing"), "invoices", "id"),
+                kind: ForeignKeyKind::Declared,
+            },
+            ForeignKey {
+                from: key(None, "orders", "userId"),
+                to: key(None, "users", "id"),
+                kind: ForeignKeyKind::Inferred,
+            },
+        ];
+
+        (nodes, keys)
+    }
+
+    #[test]
+    fn test_dot() {
+        let (nodes, keys) = example();
+        let edges: Vec<_> = keys.iter().map(|fk| (node("shop", "orders"), fk)).collect();
+        let mut output = Vec::new();
+
+        write_dot(&nodes, &edges, name, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "digraph {\n  \
+               node [shape=box];\n  \
+               \"billing.invoices\";\n  \
+               \"carts\";\n  \
+               \"orders\";\n  \
+               \"users\";\n  \
+               \"orders\" -> \"billing.invoices\" [label=\"invoiceId\"];\n  \
+               \"orders\" -> \"users\" [label=\"userId\", style=dashed];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let (nodes, keys) = example();
+        let edges: Vec<_> = keys.iter().map(|fk| (node("shop", "orders"), fk)).collect();
+        let mut output = Vec::new();
+
+        write_mermaid(&nodes, &edges, name, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "erDiagram\n  \
+               carts\n  \
+               orders }o--|| \"billing.invoices\" : \"invoiceId\"\n  \
+               orders }o..|| users : \"userId\"\n"
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(dot_string(r#"we"ird\name"#), r#""we\"ird\\name""#);
+        assert_eq!(mermaid_name("users"), "users");
+        assert_eq!(mermaid_name(r#"we"ird #1"#), r#""we#quot;ird #35;1""#);
+    }
+}
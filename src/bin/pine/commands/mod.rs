@@ -1,27 +1,71 @@
 use colored::Colorize;
 use rusty_pine::analyze::Server;
 use rusty_pine::context::{Context, ContextName};
-use rusty_pine::{cache, render};
-use std::process::exit;
+use rusty_pine::project::Project;
+use rusty_pine::{cache, render_with, RenderOptions};
+use std::env;
+use std::time::{Duration, SystemTime};
 
 pub mod analyze;
+pub mod context;
+pub mod graph;
 pub mod pine_server;
+pub mod relation;
+pub mod schema_diff;
 
-pub fn translate_one(input: String) {
-    let current_context = ContextName::current()
-        .expect("Can't find current context, create one using `pine create-context`");
-    let context: Context = cache::read(&current_context).expect(
-        "Your current context is corrupted. The data is in ~/.cache/rusty-pine/. Good luck!",
-    );
-    let server: Server =
-        cache::read(&context.server_params).expect("You must first run `pine analyze`");
-
-    let result = render(input.as_str(), &server);
-    match result {
-        Ok(output) => println!("{output}"),
-        Err(error) => {
-            eprintln!("{intro}: {error}", intro = "error".bold().red(),);
-            exit(1);
-        }
+pub fn translate_one(input: String) -> Result<(), rusty_pine::Error> {
+    // Before the context, so a broken project file is an error and not just a warning.
+    let project = Project::current()?;
+    let current_context = ContextName::current()?;
+    let context: Context = cache::read(&current_context)?;
+    let (input, options) = match project {
+        Some(project) => (project.expand_aliases(&input), project.render_options()),
+        None => (input, RenderOptions::default()),
+    };
+    let server = context.server_for(&input)?;
+
+    warn_if_outdated(&server, project);
+
+    println!("{}", render_with(input.as_str(), &server, &options)?);
+
+    Ok(())
+}
+
+/// Used when neither the project's `.pine.json` nor `$PINE_MAX_CACHE_AGE_DAYS` say otherwise.
+const DEFAULT_MAX_CACHE_AGE_DAYS: u64 = 7;
+
+/// Goes to stderr, so editors piping the query somewhere are not affected.
+fn warn_if_outdated(server: &Server, project: Option<&Project>) {
+    let Some(analyzed_at) = server.analyzed_at else {
+        return;
+    };
+    let max_age_days = project
+        .and_then(|project| project.max_cache_age_days)
+        .or_else(|| env::var("PINE_MAX_CACHE_AGE_DAYS").ok()?.parse().ok())
+        .unwrap_or(DEFAULT_MAX_CACHE_AGE_DAYS);
+    let age = SystemTime::now()
+        .duration_since(analyzed_at)
+        .unwrap_or_default();
+
+    if age > Duration::from_secs(max_age_days * 86400) {
+        eprintln!(
+            "{intro}: the database was analyzed {}, run `pine analyze --refresh` to catch up",
+            time_ago(analyzed_at),
+            intro = "warning".bold().yellow(),
+        );
+    }
+}
+
+pub fn time_ago(time: SystemTime) -> String {
+    let seconds = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or_default()
+        .as_secs();
+
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} minutes ago", seconds / 60),
+        3600..=86399 => format!("{} hours ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
     }
 }
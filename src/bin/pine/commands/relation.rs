@@ -0,0 +1,50 @@
+use crate::args::RelationCommand;
+use colored::Colorize;
+use rusty_pine::context::ContextName;
+use rusty_pine::relations::{Relation, RelationEnd, Relations};
+use rusty_pine::{cache, Error, InternalError};
+
+pub fn run(command: RelationCommand) -> Result<(), Error> {
+    let current_context = ContextName::current()?;
+    let mut relations = Relations::read(&current_context)?;
+
+    match command {
+        RelationCommand::Add { from, to } => {
+            let from: RelationEnd = from.parse()?;
+            let to: RelationEnd = to.parse()?;
+            let relation = Relation::new(from, to)?;
+
+            if relations.relations.contains(&relation) {
+                println!("Relation {} already exists", relation.to_string().bold());
+                return Ok(());
+            }
+
+            println!("Added relation {}", relation.to_string().bold());
+            relations.relations.push(relation);
+            cache::write(&relations)?;
+        }
+        RelationCommand::List => {
+            println!(
+                "Relations for context {}:",
+                current_context.to_string().bold()
+            );
+
+            for (index, relation) in relations.relations.iter().enumerate() {
+                println!("  {}: {relation}", index + 1);
+            }
+        }
+        RelationCommand::Remove { number } => {
+            if number == 0 || number > relations.relations.len() {
+                Err(InternalError(format!(
+                    "There is no relation number {number}, see `pine relation list`"
+                )))?;
+            }
+
+            let relation = relations.relations.remove(number - 1);
+            cache::write(&relations)?;
+            println!("Removed relation {}", relation.to_string().bold());
+        }
+    }
+
+    Ok(())
+}
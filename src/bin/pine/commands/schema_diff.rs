@@ -0,0 +1,203 @@
+use crate::args::SchemaDiffParams;
+use colored::Colorize;
+use rusty_pine::analyze::{diff_databases, Database, DatabaseName, SchemaChange, Server};
+use rusty_pine::context::{Context, ContextName};
+use rusty_pine::{cache, Error, InternalError};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Saves the analyzed structure of the current context, to diff against later.
+pub fn snapshot(file: PathBuf) -> Result<(), Error> {
+    let context: Context = cache::read(&ContextName::current()?)?;
+    let server = cache::read_server(&context.server_params, None)?;
+
+    cache::write_file(&server, &file)?;
+
+    println!(
+        "Saved the structure of context {} to {}",
+        context.name.to_string().bold(),
+        file.display()
+    );
+
+    Ok(())
+}
+
+/// Returns whether there are differences.
+pub fn schema_diff(params: &SchemaDiffParams) -> Result<bool, Error> {
+    let from = read_side(&params.from)?;
+    let to = read_side(&params.to)?;
+    let (from_databases, to_databases) =
+        align_databases(from.into_databases(), to.into_databases());
+
+    let changes = diff_databases(&from_databases, &to_databases);
+
+    if changes.is_empty() {
+        println!("No differences between {} and {}", params.from, params.to);
+        return Ok(false);
+    }
+
+    println!(
+        "{} differences from {} to {}:",
+        changes.len(),
+        params.from.bold(),
+        params.to.bold()
+    );
+    write_grouped(&changes, &mut io::stdout())?;
+
+    Ok(true)
+}
+
+/// Either a snapshot file, or the name of a context.
+fn read_side(name: &str) -> Result<Server, Error> {
+    if Path::new(name).is_file() {
+        return cache::read_file(Path::new(name));
+    }
+
+    // Only a missing context means the user meant something else, a broken one is reported.
+    if !cache::exists::<Context, _>(&ContextName::from(name))? {
+        Err(InternalError(format!(
+            "{name} is neither a snapshot file nor a context, see `pine list-contexts`"
+        )))?;
+    }
+    let context: Context = cache::read(&ContextName::from(name))?;
+
+    cache::read_server(&context.server_params, None)
+}
+
+/// Staging and production often use different database names on MariaDB. When both sides have a
+/// single database, we compare those no matter what they're called.
+fn align_databases(
+    from: HashMap<DatabaseName, Database>,
+    mut to: HashMap<DatabaseName, Database>,
+) -> (
+    HashMap<DatabaseName, Database>,
+    HashMap<DatabaseName, Database>,
+) {
+    if from.len() == 1 && to.len() == 1 {
+        let from_name = from.keys().next().expect("Checked above").clone();
+        let to_name = to.keys().next().expect("Checked above").clone();
+
+        if from_name != to_name {
+            println!("Comparing database {} with {}", from_name.0, to_name.0);
+
+            let mut database = to.remove(&to_name).expect("Checked above");
+            database.name = from_name.clone();
+            to.insert(from_name, database);
+        }
+    }
+
+    (from, to)
+}
+
+/// Database level changes first, then one group per changed table.
+fn write_grouped(changes: &[SchemaChange], out: &mut impl Write) -> io::Result<()> {
+    let mut current: Option<(&DatabaseName, Option<_>)> = None;
+
+    for change in changes {
+        let database = change.database();
+        let table = change.changed_table();
+
+        if current.map(|(current_database, _)| current_database) != Some(database) {
+            writeln!(out, "{}", database.0.bold())?;
+        }
+        if let Some(changed_table) = table.filter(|_| current != Some((database, table))) {
+            writeln!(out, "  {}", changed_table.to_string().bold())?;
+        }
+        current = Some((database, table));
+
+        let indent = if table.is_some() { "    " } else { "  " };
+        let line = change.describe();
+        let line = match line.chars().next() {
+            Some('+') => line.green(),
+            Some('-') => line.red(),
+            _ => line.yellow(),
+        };
+
+        writeln!(out, "{indent}{line}")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusty_pine::analyze::{diff_databases, Table};
+
+    fn database(name: &str, tables: &[(&str, &[&str])]) -> (DatabaseName, Database) {
+        let tables: HashMap<_, _> = tables
+            .iter()
+            .map(|(table, columns)| {
+                let table = Table {
+                    name: (*table).into(),
+                    kind: Default::default(),
+                    primary_key: None,
+                    columns: columns.iter().map(|&column| column.into()).collect(),
+                    foreign_keys: vec![],
+                    indexes: vec![],
+                };
+
+                (table.name.clone(), table)
+            })
+            .collect();
+
+        (
+            name.into(),
+            Database {
+                name: name.into(),
+                tables: tables.into(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_align_single_databases() {
+        let from = HashMap::from([database("shop_staging", &[("users", &["id"])])]);
+        let to = HashMap::from([database("shop", &[("users", &["id"])])]);
+
+        let (from, to) = align_databases(from, to);
+
+        assert!(diff_databases(&from, &to).is_empty());
+        assert_eq!(
+            to[&DatabaseName::from("shop_staging")].name,
+            "shop_staging".into()
+        );
+    }
+
+    #[test]
+    fn test_align_keeps_several_databases() {
+        let from = HashMap::from([database("shop", &[]), database("billing", &[])]);
+        let to = HashMap::from([database("shop_v2", &[])]);
+
+        let (_, to) = align_databases(from, to);
+
+        assert!(to.contains_key(&DatabaseName::from("shop_v2")));
+    }
+
+    #[test]
+    fn test_grouped_output() {
+        colored::control::set_override(false);
+        let from = HashMap::from([database(
+            "shop",
+            &[("carts", &["id"]), ("users", &["id", "legacyName"])],
+        )]);
+        let to = HashMap::from([database(
+            "shop",
+            &[("users", &["id", "email"]), ("orders", &["id"])],
+        )]);
+        let mut output = Vec::new();
+
+        write_grouped(&diff_databases(&from, &to), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "shop\n  \
+               - table carts\n  \
+               + table orders\n  \
+               users\n    \
+                 - column legacyName\n    \
+                 + column email\n"
+        );
+    }
+}
@@ -1,94 +1,64 @@
 mod args;
 mod commands;
 
-use crate::args::{Command, ContextParams};
+use crate::args::Command;
 use args::Args;
 use clap::Parser;
-use rusty_pine::analyze::DBType;
-use rusty_pine::context::{Context, ContextName};
-use rusty_pine::{cache, InternalError};
+use colored::Colorize;
+use commands::context;
+use rusty_pine::cache;
+use rusty_pine::context::ContextName;
+use std::process::exit;
 
 fn main() {
     env_logger::init();
 
     let args = Args::parse();
 
-    match args.command {
-        Command::CreateContext(context) => create_context(context).unwrap(),
-        Command::UseContext { name } => use_context(name).unwrap(),
-        Command::ListContexts => list_contexts().unwrap(),
-        Command::Analyze => commands::analyze::analyze().unwrap(),
-        Command::PineServer => commands::pine_server::run(),
-        Command::Translate { input } => commands::translate_one(input),
-    }
-}
-
-fn create_context(params: ContextParams) -> Result<(), rusty_pine::Error> {
-    let use_it = params.use_it;
-    let new_context: Context = params.into();
-
-    validate_new_context(&new_context)?;
-
-    cache::write(&new_context)?;
-
-    println!("Create new context \x1b[1m{}\x1b[0m.", new_context.name);
-
-    if use_it {
-        use_context(new_context.name.into())?;
-    } else {
-        println!(
-            "Switch to it by running \x1b[1mpine use-context {}\x1b[0m.",
-            new_context.name
-        );
+    if let Some(cache_dir) = args.cache_dir {
+        cache::override_cache_dir(cache_dir);
     }
 
-    Ok(())
-}
-
-fn validate_new_context(context: &Context) -> Result<(), rusty_pine::Error> {
-    if context.server_params.db_type == DBType::PostgresSQL
-        && context.server_params.default_schema.is_none()
-    {
-        Err(InternalError(
-            "You must specify the default schema when using postgres. \
-                See --help for more info"
-                .to_string(),
-        ))?;
+    if let Some(name) = args.context {
+        ContextName::override_current(name.into());
     }
 
-    Ok(())
-}
-
-fn use_context(name: String) -> Result<(), rusty_pine::Error> {
-    let context_name: ContextName = name.into();
-
-    cache::write(&context_name)?;
-
-    println!("Switched to context \x1b[1m{}\x1b[0m.", context_name);
-
-    Ok(())
-}
-
-fn list_contexts() -> Result<(), rusty_pine::Error> {
-    use colored::Colorize;
-
-    let current_context = ContextName::current()?;
-    let known_contexts: Vec<Context> = cache::read_all()?;
+    // `schema-diff` exits with 1 when there are differences.
+    let error_code = match args.command {
+        Command::SchemaDiff(_) => 2,
+        _ => 1,
+    };
+
+    let result = match args.command {
+        Command::CreateContext(params) => context::create_context(params),
+        Command::UseContext { name } => context::use_context(name),
+        Command::ListContexts => context::list_contexts(),
+        Command::DeleteContext { name } => context::delete_context(name),
+        Command::RenameContext { name, new_name } => context::rename_context(name, new_name),
+        Command::ShowContext { name } => context::show_context(name),
+        Command::EditContext(params) => context::edit_context(params),
+        Command::Analyze(params) => commands::analyze::analyze(params),
+        Command::Relation { command } => commands::relation::run(command),
+        Command::Graph(params) => commands::graph::graph(params),
+        Command::Snapshot { file } => commands::schema_diff::snapshot(file),
+        Command::SchemaDiff(params) => {
+            commands::schema_diff::schema_diff(&params).map(|differs| {
+                // Like `diff`: 1 for differences, 2 for errors. Scripts can tell them apart.
+                if differs && params.exit_code {
+                    exit(1);
+                }
+            })
+        }
+        Command::PineServer => {
+            commands::pine_server::run();
+            Ok(())
+        }
+        Command::Translate { input } => commands::translate_one(input),
+    };
 
-    println!("Available contexts:");
-    for context in &known_contexts {
-        println!(
-            "{}{}: {} ({})",
-            if current_context == context.name {
-                " * ".bold()
-            } else {
-                "   ".into()
-            },
-            context.name.to_string().bold(),
-            context.server_params.hostname,
-            context.server_params.database
-        )
+    // Scripts rely on the exit code to know if something went wrong.
+    if let Err(error) = result {
+        eprintln!("{intro}: {error}", intro = "error".bold().red());
+        exit(error_code);
     }
-
-    Ok(())
 }
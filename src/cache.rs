@@ -3,18 +3,30 @@
 //!
 //! To use the cache system, implement the Cacheable and CacheKey traits, then you can
 //! use the read() and write() functions.
-use crate::analyze::{Server, ServerParams};
+//!
+//! Files live in, by order of preference: `--cache-dir`, `$PINE_CACHE_DIR`,
+//! `$XDG_CACHE_HOME/rusty-pine` or `~/.cache/rusty-pine`.
+use crate::analyze::{DBType, DatabaseName, Server, ServerParams};
 use crate::context::{Context, ContextName};
+use crate::relations::Relations;
+use crate::InternalError;
 use log::info;
+use once_cell::sync::OnceCell;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use std::fs;
+use serde_json::Value;
 use std::fs::{read_dir, DirEntry};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+use std::{env, fs, process};
+use thiserror::Error;
 
 mod cacheable_map;
+mod server_cache;
 
 pub use cacheable_map::CacheableMap;
+pub use server_cache::{migrate_servers, read_server, write_server, CachedDatabase, ServerIndex};
 
 /// You need a cache key in order to read something for cache. Why not just use a string
 /// as a cache key? Find out in part 2...
@@ -28,11 +40,11 @@ pub trait CacheKey {
 /// with its cache key. The benefit of doing things this way is some compile time protection:
 ///
 /// ```no_run
-/// use rusty_pine::analyze::{DBType, Server, ServerParams};
-/// use rusty_pine::cache::read;
+/// use rusty_pine::analyze::{DBType, ServerParams};
+/// use rusty_pine::cache::{read, ServerIndex};
 ///
-/// let data: Server = read(
-/// //        ^^^^^^ -- since Server::CacheKey == ServerParams, trying to use a different key type
+/// let data: ServerIndex = read(
+/// //        ^^^^^^^^^^^ -- since ServerIndex::CacheKey == ServerParams, trying to use a different key type
 /// //                  will cause a compile error; trying to read() from ServerParams into
 /// //                  any struct that does not have it as a CacheKey will likewise fail
 /// //                  to compile. So we the compiler now makes sure we read and write to our
@@ -56,6 +68,49 @@ pub trait Cacheable {
     /// All structs of the same type will be saved in a similar place, so that we can answer
     /// questions like "how many Xs do we have", or do things like reading all the Ys.
     fn type_id() -> &'static str;
+
+    /// Bump this when the cached format changes in a way serde defaults can't deal with, and
+    /// teach migrate() how to upgrade the previous version.
+    fn version() -> u32 {
+        1
+    }
+
+    /// Upgrades `data` from `from_version` to the current version.
+    ///
+    /// Version 0 is anything written before we started versioning the cache. Every field added
+    /// since has a serde default, so it reads as version 1 just fine.
+    fn migrate(from_version: u32, data: Value) -> Option<Value> {
+        (from_version == 0).then_some(data)
+    }
+
+    /// The command that writes this again from scratch, if there is one.
+    fn rebuilt_by() -> Option<&'static str> {
+        None
+    }
+}
+
+/// What actually gets written to disk, so we know which build wrote it.
+#[derive(Serialize)]
+struct Envelope<'a, D> {
+    version: u32,
+    data: &'a D,
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "{} was written by {} version of pine, {}",
+    file.display(),
+    if found > expected { "a newer" } else { "an incompatible" },
+    match rebuilt_by {
+        Some(command) => format!("re-run `{command}` to rebuild it"),
+        None => "use that version or delete the file".to_string(),
+    }
+)]
+pub struct IncompatibleCacheError {
+    pub file: PathBuf,
+    pub found: u32,
+    pub expected: u32,
+    pub rebuilt_by: Option<&'static str>,
 }
 
 pub fn read<D, K>(cache_key: &K) -> Result<D, crate::Error>
@@ -70,9 +125,7 @@ where
     let file_location = get_cache_path(type_id, cache_key.as_path().as_str())?;
     info!(target: "reading cache", "Reading cache for {type_id} from {file_location:?}");
 
-    let data = serde_json::from_reader(fs::File::open(file_location)?)?;
-
-    Ok(data)
+    decode(&file_location)
 }
 
 pub fn read_all<D>() -> Result<Vec<D>, crate::Error>
@@ -83,28 +136,159 @@ where
 
     files
         .iter()
-        .map(|dir_entry| -> Result<D, crate::Error> {
-            let reader = fs::File::open(dir_entry.path())?;
-
-            Ok(serde_json::from_reader(reader)?)
-        })
+        .map(|dir_entry| decode(&dir_entry.path()))
         .collect()
 }
 
+/// Unwraps the versioned envelope, migrating older versions as needed.
+fn decode<D>(file_location: &Path) -> Result<D, crate::Error>
+where
+    D: Cacheable + DeserializeOwned,
+{
+    let broken = |error: serde_json::Error| -> crate::Error {
+        InternalError(format!("Can't read {}: {error}", file_location.display())).into()
+    };
+    let value: Value = serde_json::from_reader(fs::File::open(file_location)?).map_err(broken)?;
+    let incompatible = |found: u32| IncompatibleCacheError {
+        file: file_location.to_path_buf(),
+        found,
+        expected: D::version(),
+        rebuilt_by: D::rebuilt_by(),
+    };
+
+    let (mut version, mut data) = match value {
+        Value::Object(mut object)
+            if object.len() == 2
+                && object.contains_key("version")
+                && object.contains_key("data") =>
+        {
+            let version = object
+                .remove("version")
+                .and_then(|version| version.as_u64())
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| incompatible(0))?;
+
+            (version, object.remove("data").expect("Checked above"))
+        }
+        unversioned => (0, unversioned),
+    };
+
+    if version > D::version() {
+        Err(incompatible(version))?;
+    }
+
+    let found = version;
+    while version < D::version() {
+        data = D::migrate(version, data).ok_or_else(|| incompatible(version))?;
+        version += 1;
+    }
+
+    serde_json::from_value(data).map_err(|error| {
+        if found == D::version() {
+            // Same version, so the file itself is broken. Re-analyzing won't fix a context.
+            broken(error)
+        } else {
+            incompatible(found).into()
+        }
+    })
+}
+
 pub fn write<D, K>(data: &D) -> Result<(), crate::Error>
 where
     D: Cacheable<CacheKey = K> + Serialize,
     K: CacheKey,
 {
-    let file_location = get_cache_path(D::type_id(), data.cache_key().as_path().as_str())?;
+    let file_name = data.cache_key().as_path();
+    let file_location = get_cache_path(D::type_id(), file_name.as_str())?;
+
+    let data = serde_json::to_string(&Envelope {
+        version: D::version(),
+        data,
+    })?;
+
+    // Writing to a temporary file first, so a crash can't leave a half written file behind:
+    // renaming is atomic on the same file system. The temporary folder is next to the type
+    // folders, so listing them never finds leftovers. Threads of one process, like the ones of
+    // `pine server`, each get their own file too.
+    let temporary_location = get_cache_path(
+        TEMPORARY_FOLDER,
+        &format!(
+            "{}-{file_name}-{}-{}",
+            D::type_id(),
+            process::id(),
+            TEMPORARY_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    )?;
+
+    fs::write(&temporary_location, data)?;
+    fs::rename(&temporary_location, file_location)?;
 
-    let data = serde_json::to_string(&data)?;
+    Ok(())
+}
 
-    fs::write(file_location, data)?;
+/// Same format as write(), to a file the user picked. Snapshots, for example.
+pub fn write_file<D>(data: &D, file: &Path) -> Result<(), crate::Error>
+where
+    D: Cacheable + Serialize,
+{
+    let data = serde_json::to_string_pretty(&Envelope {
+        version: D::version(),
+        data,
+    })?;
+
+    fs::write(file, data)?;
+
+    Ok(())
+}
+
+/// Reads what write_file() wrote, migrating older versions as needed.
+pub fn read_file<D>(file: &Path) -> Result<D, crate::Error>
+where
+    D: Cacheable + DeserializeOwned,
+{
+    decode(file)
+}
+
+pub fn delete<D, K>(cache_key: &K) -> Result<(), crate::Error>
+where
+    D: Cacheable<CacheKey = K>,
+    K: CacheKey,
+{
+    let file_location = get_cache_path(D::type_id(), cache_key.as_path().as_str())?;
+    info!(target: "deleting cache", "Deleting {file_location:?}");
+
+    fs::remove_file(file_location)?;
 
     Ok(())
 }
 
+pub fn exists<D, K>(cache_key: &K) -> Result<bool, crate::Error>
+where
+    D: Cacheable<CacheKey = K>,
+    K: CacheKey,
+{
+    let file_location = get_cache_path(D::type_id(), cache_key.as_path().as_str())?;
+
+    Ok(file_location.is_file())
+}
+
+/// When was this written to the cache for the last time?
+pub fn last_written<D, K>(cache_key: &K) -> Result<SystemTime, crate::Error>
+where
+    D: Cacheable<CacheKey = K>,
+    K: CacheKey,
+{
+    let file_location = get_cache_path(D::type_id(), cache_key.as_path().as_str())?;
+
+    Ok(fs::metadata(file_location)?.modified()?)
+}
+
+/// Not a type id, but lives next to them.
+const TEMPORARY_FOLDER: &str = "tmp";
+
+/// Makes temporary file names unique within the process.
+static TEMPORARY_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
 fn get_cache_path(type_id: &'static str, cache_key: &str) -> Result<PathBuf, crate::Error> {
     let mut location = require_cache_folder(type_id)?;
 
@@ -113,12 +297,40 @@ fn get_cache_path(type_id: &'static str, cache_key: &str) -> Result<PathBuf, cra
     Ok(location)
 }
 
+/// Set by `pine --cache-dir <path>`.
+static CACHE_DIR_OVERRIDE: OnceCell<PathBuf> = OnceCell::new();
+
+/// Can only be done once, before anything is read from or written to the cache.
+pub fn override_cache_dir(path: PathBuf) {
+    CACHE_DIR_OVERRIDE
+        .set(path)
+        .expect("The cache folder can only be overridden once");
+}
+
+fn cache_root(
+    cache_dir_override: Option<&Path>,
+    var: impl Fn(&str) -> Option<String>,
+) -> Option<PathBuf> {
+    // Empty variables are treated as unset, same as the XDG spec says.
+    let var = |name: &str| var(name).filter(|value| !value.is_empty());
+
+    cache_dir_override
+        .map(Path::to_path_buf)
+        .or_else(|| var("PINE_CACHE_DIR").map(PathBuf::from))
+        .or_else(|| var("XDG_CACHE_HOME").map(|xdg| PathBuf::from(xdg).join("rusty-pine")))
+        .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".cache").join("rusty-pine")))
+}
+
 fn require_cache_folder(type_id: &'static str) -> Result<PathBuf, crate::Error> {
-    let home = std::env::var("HOME")?;
+    let mut path = cache_root(CACHE_DIR_OVERRIDE.get().map(PathBuf::as_path), |name| {
+        env::var(name).ok()
+    })
+    .ok_or_else(|| {
+        InternalError(
+            "Can't find a cache folder, set HOME, XDG_CACHE_HOME or PINE_CACHE_DIR".to_string(),
+        )
+    })?;
 
-    let mut path = PathBuf::from(home);
-    path.push(".cache");
-    path.push("rusty-pine");
     path.push("cache");
     path.push("v2");
     path.push(type_id);
@@ -138,7 +350,7 @@ fn list_cached_files_of_type(type_id: &'static str) -> Result<Vec<DirEntry>, cra
 
 // Please dump all impls here, so we keep the rest of the code base clean.
 
-impl Cacheable for Server {
+impl Cacheable for ServerIndex {
     type CacheKey = ServerParams;
 
     fn cache_key(&self) -> Self::CacheKey {
@@ -148,14 +360,81 @@ impl Cacheable for Server {
     fn type_id() -> &'static str {
         "server"
     }
+
+    /// Version 1 had all the databases in the same file, there's nothing to migrate from it
+    /// without reading the server again.
+    fn version() -> u32 {
+        2
+    }
+
+    fn rebuilt_by() -> Option<&'static str> {
+        Some("pine analyze")
+    }
+}
+
+/// Only written with write_file(), as a snapshot of the analyzed structure. Snapshots from before
+/// they were versioned are version 0, which reads fine.
+impl Cacheable for Server {
+    type CacheKey = ServerParams;
+
+    fn cache_key(&self) -> Self::CacheKey {
+        self.params.clone()
+    }
+
+    fn type_id() -> &'static str {
+        "snapshot"
+    }
+
+    fn rebuilt_by() -> Option<&'static str> {
+        Some("pine snapshot")
+    }
 }
 
 impl CacheKey for ServerParams {
     fn as_path(&self) -> String {
-        format!("server-{}-{}-{}.json", self.hostname, self.port, self.user)
+        format!("{}.json", server_file_stem(self))
+    }
+}
+
+impl Cacheable for CachedDatabase {
+    type CacheKey = (ServerParams, DatabaseName);
+
+    fn cache_key(&self) -> Self::CacheKey {
+        (self.params.clone(), self.database.name.clone())
+    }
+
+    fn type_id() -> &'static str {
+        "database"
+    }
+
+    fn rebuilt_by() -> Option<&'static str> {
+        Some("pine analyze")
+    }
+}
+
+impl CacheKey for (ServerParams, DatabaseName) {
+    fn as_path(&self) -> String {
+        format!("{}--{}.json", server_file_stem(&self.0), self.1 .0)
     }
 }
 
+fn server_file_stem(params: &ServerParams) -> String {
+    let db_type = match params.db_type {
+        DBType::PostgresSQL => "postgres",
+        DBType::MariaDB => "mariadb",
+    };
+    let schema = match &params.default_schema {
+        Some(schema) => format!("-{}", schema.0),
+        None => String::new(),
+    };
+
+    // Contexts on the same server but different databases need their own files.
+    format!(
+        "server-{db_type}-{}-{}-{}-{}{schema}",
+        params.hostname, params.port, params.user, params.database.0
+    )
+}
+
 impl Cacheable for Context {
     type CacheKey = ContextName;
 
@@ -174,6 +453,18 @@ impl CacheKey for ContextName {
     }
 }
 
+impl Cacheable for Relations {
+    type CacheKey = ContextName;
+
+    fn cache_key(&self) -> Self::CacheKey {
+        self.context.clone()
+    }
+
+    fn type_id() -> &'static str {
+        "relations"
+    }
+}
+
 impl Cacheable for ContextName {
     type CacheKey = SharedCacheKey;
 
@@ -193,3 +484,64 @@ impl CacheKey for SharedCacheKey {
         self.0.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn test_cache_root_preference() {
+        let vars = |names: &'static [(&str, &str)]| {
+            move |name: &str| {
+                names
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        let all: &'static [(&str, &str)] = &[
+            ("HOME", "/home/pine"),
+            ("XDG_CACHE_HOME", "/xdg"),
+            ("PINE_CACHE_DIR", "/pine"),
+        ];
+
+        assert_eq!(
+            cache_root(Some(Path::new("/flag")), vars(all)),
+            Some(PathBuf::from("/flag"))
+        );
+        assert_eq!(cache_root(None, vars(all)), Some(PathBuf::from("/pine")));
+        assert_eq!(
+            cache_root(
+                None,
+                vars(&[("HOME", "/home/pine"), ("XDG_CACHE_HOME", "")])
+            ),
+            Some(PathBuf::from("/home/pine/.cache/rusty-pine"))
+        );
+        assert_eq!(cache_root(None, vars(&[])), None);
+    }
+
+    #[test]
+    fn test_decode_versions() {
+        // Separate test runs can happen at the same time.
+        let file = env::temp_dir().join(format!("rusty-pine-test-decode-{}.json", process::id()));
+        let decode_json = |json: &str| {
+            fs::write(&file, json).unwrap();
+            decode::<ContextName>(&file)
+        };
+
+        let unversioned = decode_json(r#""local""#).unwrap();
+        let current = decode_json(r#"{"version": 1, "data": "local"}"#).unwrap();
+        let newer = decode_json(r#"{"version": 2, "data": "local"}"#).unwrap_err();
+        let broken = decode_json(r#"{"version": 1, "data": 42}"#).unwrap_err();
+        fs::remove_file(&file).unwrap();
+
+        assert_eq!(unversioned, "local".into());
+        assert_eq!(current, "local".into());
+        assert!(matches!(
+            newer.into_inner(),
+            ErrorKind::IncompatibleCacheError(IncompatibleCacheError { found: 2, .. })
+        ));
+        assert!(matches!(broken.into_inner(), ErrorKind::InternalError(_)));
+    }
+}
@@ -28,7 +28,11 @@ where
         self.inner.get(key)
     }
 
-    pub fn iter(&self) -> Iter<K, V> {
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.inner.get_mut(key)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
         self.inner.iter()
     }
 }
@@ -0,0 +1,158 @@
+//! Servers with thousands of tables make for huge JSON files, and reading all of it for every
+//! pine is slow. So each database gets its own file, next to a small index of the server, and
+//! we only read the databases a pine needs.
+use crate::analyze::{Database, DatabaseName, Server, ServerParams};
+use crate::cache::{
+    decode, get_cache_path, list_cached_files_of_type, read, write, CacheKey, Cacheable,
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::SystemTime;
+
+/// What we know about a server without reading any of its databases.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerIndex {
+    pub params: ServerParams,
+    pub databases: Vec<DatabaseName>,
+    #[serde(default)]
+    pub analyzed_at: Option<SystemTime>,
+    #[serde(default)]
+    pub infers_foreign_keys: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedDatabase {
+    pub params: ServerParams,
+    pub database: Database,
+}
+
+/// The index is written last, so it never mentions a database we didn't write yet.
+pub fn write_server(server: &Server) -> Result<(), crate::Error> {
+    for database in server.databases().values() {
+        write(&CachedDatabase {
+            params: server.params.clone(),
+            database: database.clone(),
+        })?;
+    }
+
+    write(&ServerIndex {
+        params: server.params.clone(),
+        databases: server.databases().keys().cloned().collect(),
+        analyzed_at: server.analyzed_at,
+        infers_foreign_keys: server.infers_foreign_keys,
+    })
+}
+
+/// Reads the given databases, or all of them for `None`. Databases that foreign keys point to
+/// are read as well, otherwise we couldn't join to them. Databases the server doesn't have are
+/// ignored, resolving the pine will complain about them.
+pub fn read_server(
+    params: &ServerParams,
+    databases: Option<&[DatabaseName]>,
+) -> Result<Server, crate::Error> {
+    let index: ServerIndex = read(params)?;
+    let mut pending: Vec<DatabaseName> = match databases {
+        Some(databases) => databases.to_vec(),
+        None => index.databases.clone(),
+    };
+    let mut loaded = HashMap::new();
+
+    while let Some(name) = pending.pop() {
+        if loaded.contains_key(&name) || !index.databases.contains(&name) {
+            continue;
+        }
+
+        let cached: CachedDatabase = read(&(index.params.clone(), name.clone()))?;
+
+        pending.extend(
+            cached
+                .database
+                .tables
+                .iter()
+                .flat_map(|(_, table)| &table.foreign_keys)
+                .filter_map(|foreign_key| foreign_key.to.database.clone()),
+        );
+        loaded.insert(name, cached.database);
+    }
+
+    let unloaded_databases = index
+        .databases
+        .into_iter()
+        .filter(|name| !loaded.contains_key(name))
+        .collect();
+    let mut server = Server::new(index.params, loaded);
+    server.analyzed_at = index.analyzed_at;
+    server.infers_foreign_keys = index.infers_foreign_keys;
+    server.unloaded_databases = unloaded_databases;
+
+    Ok(server)
+}
+
+/// Before each database got its own file, the whole server was in a single file, named without
+/// the database type, database and schema. This splits those files into the current layout.
+///
+/// Current index files are a newer version than those, so they are left alone and this is safe
+/// to call any number of times. Returns how many servers were migrated.
+pub fn migrate_servers() -> Result<usize, crate::Error> {
+    let mut migrated = 0;
+
+    for dir_entry in list_cached_files_of_type(ServerIndex::type_id())? {
+        let path = dir_entry.path();
+        let server: Server = match decode(&path) {
+            Ok(server) => server,
+            Err(error) => {
+                info!(target: "migrating cache", "Not an old server file {path:?}: {error}");
+                continue;
+            }
+        };
+
+        info!(target: "migrating cache", "Splitting {path:?} into one file per database");
+        write_server(&server)?;
+
+        // The old names never have the database type in them, but let's not delete the new index.
+        if get_cache_path(ServerIndex::type_id(), &server.params.as_path())? != path {
+            fs::remove_file(&path)?;
+        }
+        migrated += 1;
+    }
+
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CACHE_DIR_OVERRIDE;
+    use crate::engine::sql::fixtures::{server, table};
+    use std::env;
+
+    #[test]
+    fn test_migrate_servers() {
+        // No other test uses the cache folder.
+        let root = CACHE_DIR_OVERRIDE.get_or_init(|| {
+            env::temp_dir().join(format!("rusty-pine-test-cache-{}", std::process::id()))
+        });
+        let mut old = server(vec![
+            ("shop", vec![table("users", &["id"])]),
+            ("billing", vec![table("invoices", &["id"])]),
+        ]);
+        old.params.hostname = "localhost".to_string();
+        old.params.port = 3306;
+        old.params.user = "root".to_string();
+        let old_file =
+            get_cache_path(ServerIndex::type_id(), "server-localhost-3306-root.json").unwrap();
+        fs::write(&old_file, serde_json::to_string(&old).unwrap()).unwrap();
+
+        let migrated = migrate_servers().unwrap();
+        let migrated_again = migrate_servers().unwrap();
+        let server = read_server(&old.params, None).unwrap();
+        fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(migrated, 1);
+        assert_eq!(migrated_again, 0);
+        assert!(!old_file.exists());
+        assert_eq!(server.databases().len(), 2);
+    }
+}
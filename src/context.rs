@@ -1,8 +1,17 @@
-use crate::analyze::ServerParams;
+use crate::analyze::{DatabaseName, Server, ServerParams};
 use crate::cache;
-use crate::cache::Cacheable;
+use crate::cache::{Cacheable, ServerIndex};
+use crate::error::ErrorKind;
+use crate::project::Project;
+use crate::relations::Relations;
+use crate::{referenced_databases, InternalError};
+use colored::Colorize;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use std::io::ErrorKind as IoErrorKind;
+
+mod url;
 
 #[derive(Serialize, Deserialize)]
 pub struct Context {
@@ -10,14 +19,129 @@ pub struct Context {
     pub server_params: ServerParams,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct ContextName(String);
 
+impl Context {
+    /// Reads the whole analyzed server structure, including the relations the user added.
+    pub fn server(&self) -> Result<Server, crate::Error> {
+        self.read_server(None)
+    }
+
+    /// Same as server(), but only reads the databases `input` needs.
+    pub fn server_for(&self, input: &str) -> Result<Server, crate::Error> {
+        match referenced_databases(input)? {
+            Some(mut databases) => {
+                databases.extend(self.server_params.default_database().cloned());
+
+                self.read_server(Some(databases))
+            }
+            None => self.server(),
+        }
+    }
+
+    /// Relations saved with `pine relation add`, plus the ones from the project's `.pine.json`.
+    pub fn relations(&self) -> Result<Relations, crate::Error> {
+        let mut relations = Relations::read(&self.name)?;
+
+        if let Some(project) = Project::current()? {
+            relations
+                .relations
+                .extend(project.relations(&self.name)?.relations);
+        }
+
+        Ok(relations)
+    }
+
+    fn read_server(&self, databases: Option<Vec<DatabaseName>>) -> Result<Server, crate::Error> {
+        let relations = self.relations()?;
+        let databases = databases.map(|mut databases| {
+            let relation_databases = relations
+                .relations
+                .iter()
+                .flat_map(|relation| [&relation.from.database, &relation.to.database])
+                .flatten();
+            databases.extend(relation_databases.cloned());
+
+            databases
+        });
+
+        // Analyses from before each database got its own file are split up the first time
+        // they're needed.
+        if !cache::exists::<ServerIndex, _>(&self.server_params)? {
+            cache::migrate_servers()?;
+        }
+
+        let mut server =
+            cache::read_server(&self.server_params, databases.as_deref()).map_err(|error| {
+                match error.into_inner() {
+                    ErrorKind::IoError(io_error) if io_error.kind() == IoErrorKind::NotFound => {
+                        InternalError(format!(
+                            "Context {} was not analyzed yet, run `pine analyze` first",
+                            self.name
+                        ))
+                        .into()
+                    }
+                    error => crate::Error::from(error),
+                }
+            })?;
+
+        server.add_relations(&relations);
+
+        Ok(server)
+    }
+}
+
+/// Set by `pine --context <name>`, so a single command can use a different context without
+/// switching the current one.
+static CONTEXT_OVERRIDE: OnceCell<ContextName> = OnceCell::new();
+
 impl ContextName {
+    /// In order: `--context`, the project's `.pine.json`, then `pine use-context`.
     pub fn current() -> Result<ContextName, crate::Error> {
+        if let Some(context) = CONTEXT_OVERRIDE.get() {
+            return Ok(context.clone());
+        }
+
+        // A broken project file shouldn't keep `use-context` and the like from working. Commands
+        // using the rest of the project settings will report it.
+        match Project::current() {
+            Ok(project) => {
+                if let Some(context) = project.and_then(|project| project.context.clone()) {
+                    return Ok(context);
+                }
+            }
+            Err(error) => eprintln!(
+                "{intro}: ignoring the project file, falling back to the saved context\n{error}",
+                intro = "warning".bold().yellow()
+            ),
+        }
+
+        Self::persisted()
+    }
+
+    /// The context selected with `pine use-context`.
+    pub fn persisted() -> Result<ContextName, crate::Error> {
         // All context names use the cache key, because that's how we save the current context.
         // Reading a context named "any" will just get us the current context.
-        cache::read(&ContextName("any".to_string()).cache_key())
+        cache::read(&ContextName("any".to_string()).cache_key()).map_err(|error| {
+            match error.into_inner() {
+                ErrorKind::IoError(io_error) if io_error.kind() == IoErrorKind::NotFound => {
+                    InternalError(
+                        "No context selected, create one using `pine create-context`".to_string(),
+                    )
+                    .into()
+                }
+                error => error.into(),
+            }
+        })
+    }
+
+    /// Can only be done once, before anything reads the current context.
+    pub fn override_current(context: ContextName) {
+        CONTEXT_OVERRIDE
+            .set(context)
+            .expect("The current context can only be overridden once");
     }
 }
 
@@ -0,0 +1,311 @@
+//! Passwords are never stored by pine, but the standard client credential files already exist on
+//! most machines. We look for the password there before asking for it:
+//! - `~/.pgpass` (or `$PGPASSFILE`) and `pg_service.conf` files for Postgres
+//! - the `[client]` section of `~/.my.cnf` for MariaDB
+use crate::analyze::{DBType, ServerParams};
+use log::warn;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+pub struct FoundPassword {
+    pub password: String,
+    /// Where we found it, so we can tell the user.
+    pub file: PathBuf,
+}
+
+/// Finds the password in the content of a credentials file.
+type PasswordLookup = fn(&str, &ServerParams) -> Option<String>;
+
+/// Missing or unreadable files are skipped, they're only a convenience.
+pub fn find_password(params: &ServerParams) -> Option<FoundPassword> {
+    let mut sources: Vec<(PathBuf, PasswordLookup)> = Vec::new();
+
+    match params.db_type {
+        DBType::PostgresSQL => {
+            sources.extend(pgpass_file().map(|file| (file, pgpass_password as PasswordLookup)));
+            for file in pg_service_files() {
+                sources.push((file, pg_service_password));
+            }
+        }
+        DBType::MariaDB => {
+            sources
+                .extend(home_file(".my.cnf").map(|file| (file, my_cnf_password as PasswordLookup)));
+        }
+    }
+
+    sources.into_iter().find_map(|(file, find)| {
+        let content = fs::read_to_string(&file).ok()?;
+        let password = find(&content, params)?;
+
+        Some(FoundPassword { password, file })
+    })
+}
+
+fn home_file(name: &str) -> Option<PathBuf> {
+    env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join(name))
+}
+
+/// Like libpq, we ignore the file if anyone but its owner can read it.
+fn pgpass_file() -> Option<PathBuf> {
+    env::var("PGPASSFILE")
+        .ok()
+        .map(PathBuf::from)
+        .or_else(|| home_file(".pgpass"))
+        .filter(|file| is_private(file))
+}
+
+#[cfg(unix)]
+fn is_private(file: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    let Ok(metadata) = fs::metadata(file) else {
+        return true;
+    };
+    let private = metadata.permissions().mode() & 0o077 == 0;
+
+    if !private {
+        warn!(
+            "ignoring {}: group or others can read it, it should be 0600 or less",
+            file.display()
+        );
+    }
+
+    private
+}
+
+#[cfg(not(unix))]
+fn is_private(_file: &Path) -> bool {
+    true
+}
+
+/// Same order libpq uses: the user's file first, then the system wide one.
+fn pg_service_files() -> Vec<PathBuf> {
+    let user_file = env::var("PGSERVICEFILE")
+        .ok()
+        .map(PathBuf::from)
+        .or_else(|| home_file(".pg_service.conf"));
+    let system_file = env::var("PGSYSCONFDIR")
+        .ok()
+        .map(|dir| PathBuf::from(dir).join("pg_service.conf"));
+
+    user_file.into_iter().chain(system_file).collect()
+}
+
+/// Each line looks like `hostname:port:database:username:password`. The first four fields can be
+/// `*`, which matches anything. Colons and backslashes in fields are escaped with a backslash.
+fn pgpass_password(content: &str, params: &ServerParams) -> Option<String> {
+    let port = params.port.to_string();
+    let expected = [
+        params.hostname.as_str(),
+        port.as_str(),
+        params.database.0.as_str(),
+        params.user.as_str(),
+    ];
+
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .map(split_pgpass_line)
+        .find(|fields| {
+            fields.len() == 5
+                && fields
+                    .iter()
+                    .zip(expected)
+                    .all(|(field, expected)| field == "*" || field == expected)
+        })
+        .map(|mut fields| fields.remove(4))
+}
+
+fn split_pgpass_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => fields
+                .last_mut()
+                .expect("There's always one field")
+                .extend(chars.next()),
+            ':' => fields.push(String::new()),
+            char => fields
+                .last_mut()
+                .expect("There's always one field")
+                .push(char),
+        }
+    }
+
+    fields
+}
+
+/// Only the `[client]` section is used. If it mentions a user, host or port, they have to match
+/// the context.
+fn my_cnf_password(content: &str, params: &ServerParams) -> Option<String> {
+    let sections = ini_sections(content);
+    let client = sections.iter().find(|(name, _)| name == "client")?;
+
+    options_match(
+        &client.1,
+        &[
+            ("user", params.user.as_str()),
+            ("host", params.hostname.as_str()),
+            ("port", params.port.to_string().as_str()),
+        ],
+    )
+    .then(|| client.1.get("password").cloned())
+    .flatten()
+}
+
+/// Contexts don't know about service names, so like libpq we only use the service `$PGSERVICE`
+/// names. Guessing could send the password of one server to another.
+fn pg_service_password(content: &str, params: &ServerParams) -> Option<String> {
+    service_password(content, &env::var("PGSERVICE").ok()?, params)
+}
+
+/// The options the service has still have to match the context.
+fn service_password(content: &str, service: &str, params: &ServerParams) -> Option<String> {
+    let port = params.port.to_string();
+    let expected = [
+        ("host", params.hostname.as_str()),
+        ("port", port.as_str()),
+        ("dbname", params.database.0.as_str()),
+        ("user", params.user.as_str()),
+    ];
+
+    ini_sections(content)
+        .into_iter()
+        .find(|(name, _)| name == service)
+        .filter(|(_, options)| options_match(options, &expected))
+        .and_then(|(_, mut options)| options.remove("password"))
+}
+
+/// Options that are not there match anything.
+fn options_match(options: &HashMap<String, String>, expected: &[(&str, &str)]) -> bool {
+    expected.iter().all(|(key, expected)| {
+        options
+            .get(*key)
+            .is_none_or(|value| value.as_str() == *expected)
+    })
+}
+
+/// Both my.cnf and pg_service.conf are simple INI files.
+fn ini_sections(content: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut sections: Vec<(String, HashMap<String, String>)> = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((name.trim().to_string(), HashMap::new()));
+        } else if let (Some((key, value)), Some((_, options))) =
+            (line.split_once('='), sections.last_mut())
+        {
+            // my.cnf allows both "some-option" and "some_option".
+            let key = key.trim().replace('-', "_");
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+
+            options.insert(key, value.to_string());
+        }
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(db_type: DBType) -> ServerParams {
+        ServerParams {
+            db_type,
+            hostname: "db.local".to_string(),
+            port: 5432,
+            user: "pine".to_string(),
+            database: "app".into(),
+            default_schema: None,
+        }
+    }
+
+    #[test]
+    fn test_pgpass_matching() {
+        let content = "\
+# comments are ignored
+other.local:5432:app:pine:wrong
+db.local:*:app:pine:pa\\:ss\\\\word
+*:*:*:*:fallback
+";
+
+        let password = pgpass_password(content, &params(DBType::PostgresSQL));
+
+        assert_eq!(password.as_deref(), Some("pa:ss\\word"));
+    }
+
+    #[test]
+    fn test_my_cnf_client_section() {
+        let content = "\
+[mysql]
+password = wrong
+
+[client]
+user = pine
+password = \"secret\"
+";
+
+        let password = my_cnf_password(content, &params(DBType::MariaDB));
+
+        assert_eq!(password.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_my_cnf_other_user() {
+        let content = "[client]\nuser=root\npassword=secret\n";
+
+        assert_eq!(my_cnf_password(content, &params(DBType::MariaDB)), None);
+    }
+
+    #[test]
+    fn test_pg_service_matching() {
+        let content = "\
+[staging]
+host=staging.local
+password=wrong
+
+[local]
+host=db.local
+dbname=app
+password=secret
+";
+
+        let params = params(DBType::PostgresSQL);
+
+        assert_eq!(
+            service_password(content, "local", &params).as_deref(),
+            Some("secret")
+        );
+        assert_eq!(service_password(content, "staging", &params), None);
+        assert_eq!(service_password(content, "missing", &params), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pgpass_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // Separate test runs can happen at the same time.
+        let file = env::temp_dir().join(format!("rusty-pine-test-pgpass-{}", std::process::id()));
+        fs::write(&file, "*:*:*:*:secret").unwrap();
+
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o600)).unwrap();
+        let private = is_private(&file);
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o644)).unwrap();
+        let readable = is_private(&file);
+        fs::remove_file(&file).unwrap();
+
+        assert!(private);
+        assert!(!readable);
+    }
+}
@@ -7,12 +7,19 @@ mod syntax;
 #[cfg(test)]
 mod tests;
 
-use crate::analyze::Server;
+use crate::analyze::{DatabaseName, Server};
 pub use syntax::Rule;
 
-use crate::engine::query_builder::{build_query, get_columns, get_neighbors};
-use crate::engine::rendering::{render_columns, render_neighbors, render_query};
-use crate::engine::syntax::{parse_to_stage4, Stage4Rep};
+use crate::engine::query_builder::{
+    build_query, expand_table, get_columns, get_databases, get_indexes, get_neighbor_tree,
+    get_neighbors, get_tables,
+};
+use crate::engine::rendering::{
+    render_columns, render_databases, render_indexes, render_neighbor_tree, render_neighbors,
+    render_query, render_tables,
+};
+use crate::engine::syntax::{parse_to_stage4, OptionalInput, Stage4Rep, TableInput};
+use query_builder::LiteralValue;
 
 use log::info;
 pub use query_builder::QueryBuildError;
@@ -20,7 +27,22 @@ use std::fmt::Debug;
 use std::ops::Range;
 use thiserror::Error;
 
+/// Tweaks to how pines are translated, usually coming from a project's `.pine.json`.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Used when the pine does not specify a limit, instead of 10.
+    pub default_limit: Option<usize>,
+}
+
 pub fn render(input: &str, server: &Server) -> Result<String, crate::error::Error> {
+    render_with(input, server, &RenderOptions::default())
+}
+
+pub fn render_with(
+    input: &str,
+    server: &Server,
+    options: &RenderOptions,
+) -> Result<String, crate::error::Error> {
     info!("translating {input}");
 
     let pine = parse_to_stage4(input)?;
@@ -29,23 +51,84 @@ pub fn render(input: &str, server: &Server) -> Result<String, crate::error::Erro
 
     match pine {
         Stage4Rep::Query(query) => {
-            let query = map_err(input, build_query(query, server))?;
+            let mut query = map_err(input, build_query(query, server))?;
+
+            if let (LimitHolder::Implicit(), Some(default_limit)) =
+                (&query.limit.it, options.default_limit)
+            {
+                query.limit.it = LimitHolder::RowCount(Sourced::implicit(LiteralValue::Number(
+                    default_limit.to_string(),
+                )));
+            }
 
             Ok(render_query(query))
         }
-        Stage4Rep::ShowNeighbors(for_table) => {
+        Stage4Rep::ShowNeighbors(for_table, None) => {
+            let for_table = map_err(input, expand_table(for_table, server))?;
             let neighbors = map_err(input, get_neighbors(for_table, server))?;
 
             Ok(render_neighbors(neighbors))
         }
+        Stage4Rep::ShowNeighbors(for_table, Some(depth)) => {
+            let for_table = map_err(input, expand_table(for_table, server))?;
+            let neighbors = map_err(input, get_neighbor_tree(for_table, depth, server))?;
+
+            Ok(render_neighbor_tree(for_table.it, depth, &neighbors))
+        }
         Stage4Rep::ShowColumns(for_table) => {
+            let for_table = map_err(input, expand_table(for_table, server))?;
             let columns = map_err(input, get_columns(for_table, server))?;
 
             Ok(render_columns(for_table.it, columns))
         }
+        Stage4Rep::ShowIndexes(for_table) => {
+            let for_table = map_err(input, expand_table(for_table, server))?;
+            let indexes = map_err(input, get_indexes(for_table, server))?;
+
+            Ok(render_indexes(for_table.it, indexes))
+        }
+        Stage4Rep::ShowTables(filter) => {
+            let (database, tables) = map_err(input, get_tables(filter, server))?;
+
+            Ok(render_tables(database, filter, &tables))
+        }
+        Stage4Rep::ShowDatabases => Ok(render_databases(&server.params, &get_databases(server))),
     }
 }
 
+/// The databases a pine mentions explicitly, or `None` if it might need any of them. This way we
+/// can skip reading the rest from the cache.
+pub fn referenced_databases(input: &str) -> Result<Option<Vec<DatabaseName>>, crate::error::Error> {
+    let explicit_database = |table: &Sourced<TableInput>| match &table.it.database {
+        OptionalInput::Specified(database) => Some(DatabaseName(database.it.name.to_string())),
+        OptionalInput::Implicit => None,
+    };
+
+    let databases = match parse_to_stage4(input)? {
+        Stage4Rep::Query(query) => {
+            let joined_tables = query
+                .joins
+                .iter()
+                .flat_map(|join| [&join.it.source_table, &join.it.target_table]);
+
+            std::iter::once(&query.from)
+                .chain(joined_tables)
+                .filter_map(explicit_database)
+                .collect()
+        }
+        // Tables from any database might point to this one.
+        Stage4Rep::ShowNeighbors(..) => return Ok(None),
+        Stage4Rep::ShowColumns(table) | Stage4Rep::ShowIndexes(table) => {
+            explicit_database(&table).into_iter().collect()
+        }
+        Stage4Rep::ShowTables(_) => vec![],
+        // Listing every database means reading every database.
+        Stage4Rep::ShowDatabases => return Ok(None),
+    };
+
+    Ok(Some(databases))
+}
+
 #[derive(Debug, Error)]
 pub struct RenderingError {
     pub input: String,
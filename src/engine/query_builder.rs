@@ -4,7 +4,8 @@ use std::fmt::Debug;
 use thiserror::Error;
 
 use crate::analyze::{
-    Column, ColumnName, DatabaseName, ForeignKey, Server, ServerParams, TableName,
+    Column, ColumnName, DatabaseName, ForeignKey, ForeignKeyKind, Index, Server, ServerParams,
+    TableKind, TableName,
 };
 use crate::engine::syntax::{Stage4ComputationInput, Stage4Query, TableInput};
 use crate::engine::{
@@ -13,10 +14,17 @@ use crate::engine::{
 };
 use sql_introspection::Introspective;
 
+mod abbreviations;
 mod sql_introspection;
 mod stage5;
+mod suggestions;
+
+pub use abbreviations::expand_table;
 
 pub fn build_query(input: Stage4Query<'_>, server: &Server) -> Result<Query, QueryBuildError> {
+    info!("expanding abbreviations");
+    let input = abbreviations::expand_query(input, server)?;
+
     info!("creating stage 5 builder");
     let builder = stage5::Stage5Builder::new(input, server);
 
@@ -34,6 +42,54 @@ pub fn get_neighbors(
     Ok(neighboring_tables)
 }
 
+pub fn get_neighbor_tree(
+    for_table: Sourced<TableInput>,
+    depth: usize,
+    server: &Server,
+) -> Result<Vec<Neighbor>, QueryBuildError> {
+    info!(
+        "showing neighbors for '{}', {depth} levels deep",
+        for_table.it.table.it.name
+    );
+
+    server.neighbor_tree(for_table, depth)
+}
+
+/// A table in a neighbor tree, joined to its parent table.
+#[derive(Debug, Clone)]
+pub struct Neighbor {
+    pub direction: NeighborDirection,
+    /// Always goes from the parent to this table, even for incoming keys.
+    pub foreign_key: ForeignKey,
+    /// `None` for the default database, because pines don't need to mention it.
+    pub database: Option<DatabaseName>,
+    /// Only the first time a table shows up in the tree do we list its own neighbors.
+    pub children: Vec<Neighbor>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighborDirection {
+    /// The parent table declares the key.
+    Outgoing,
+    /// This table declares a key pointing to the parent.
+    Incoming,
+}
+
+pub fn get_tables<'a>(
+    filter: Option<&str>,
+    server: &'a Server,
+) -> Result<(&'a analyze::Database, Vec<&'a analyze::Table>), QueryBuildError> {
+    server.tables(filter)
+}
+
+/// Sorted by name.
+pub fn get_databases(server: &Server) -> Vec<&DatabaseName> {
+    let mut databases: Vec<_> = server.databases().keys().collect();
+    databases.sort_by(|a, b| a.0.cmp(&b.0));
+
+    databases
+}
+
 pub fn get_columns<'a>(
     for_table: Sourced<TableInput>,
     server: &'a Server,
@@ -41,12 +97,34 @@ pub fn get_columns<'a>(
     server.columns(for_table)
 }
 
+pub fn get_indexes<'a>(
+    for_table: Sourced<TableInput>,
+    server: &'a Server,
+) -> Result<&'a [Index], QueryBuildError> {
+    server.indexes(for_table)
+}
+
 #[derive(Error, Debug, Clone)]
 pub enum QueryBuildError {
     InvalidPostgresConfig,
     DefaultDatabaseNotFound(ServerParams),
-    DatabaseNotFound(Sourced<DatabaseName>),
-    TableNotFound(Sourced<analyze::TableName>),
+    /// Also has the names closest to the one we didn't find.
+    DatabaseNotFound(Sourced<DatabaseName>, Vec<String>),
+    TableNotFound(Sourced<analyze::TableName>, Vec<String>),
+    ColumnNotFound(
+        Sourced<analyze::TableName>,
+        Sourced<ColumnName>,
+        Vec<String>,
+    ),
+    /// The abbreviation could mean any of these tables.
+    AmbiguousTable(Sourced<analyze::TableName>, Vec<String>),
+    AmbiguousColumn(
+        Sourced<analyze::TableName>,
+        Sourced<ColumnName>,
+        Vec<String>,
+    ),
+    /// An unqualified column that isn't in the previous table, but is in several others.
+    ColumnInSeveralTables(Sourced<ColumnName>, Vec<String>),
     InvalidForeignKey {
         from: Sourced<analyze::TableName>,
         to: Sourced<analyze::TableName>,
@@ -60,6 +138,11 @@ pub enum QueryBuildError {
         analyze::Key,
         Sourced<LiteralValue>,
     ),
+    MissingPrimaryKey(
+        Sourced<analyze::TableName>,
+        TableKind,
+        Sourced<LiteralValue>,
+    ),
 }
 
 #[derive(Debug)]
@@ -71,6 +154,14 @@ pub struct Query {
     pub orders: Vec<Sourced<OrderHolder<Selectable>>>,
     pub group_by: Vec<Sourced<Selectable>>,
     pub limit: Sourced<LimitHolder<LiteralValue>>,
+    /// Things that don't stop us from building the query, but the user should know about.
+    pub warnings: Vec<QueryWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryWarning {
+    /// Filtering or ordering by a column no index starts with can lead to a full table scan.
+    UnindexedColumn(Table, ColumnName),
 }
 
 pub type Selectable = SelectableHolder<Condition, Computation>;
@@ -109,6 +200,8 @@ pub struct ExplicitJoin {
     /// The table to join to.
     pub target_table: Sourced<Table>,
     pub conditions: Vec<Sourced<Condition>>,
+    /// The kind of foreign key we used to join automatically, if we did.
+    pub key_kind: Option<ForeignKeyKind>,
 }
 
 pub type LiteralValue = LiteralValueHolder<String>;
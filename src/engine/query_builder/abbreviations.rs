@@ -0,0 +1,498 @@
+//! Table and column names get long, and typing less is the whole point of pine. So names can be
+//! abbreviated, as long as only one table or column matches:
+//! - by a prefix: `userPr` is `userPreferences`
+//! - by the start of each word: `uP` is `userPreferences`, `crAt` is `createdAt` or `created_at`
+//!
+//! Names that don't match anything are left alone, so errors mention what the user typed.
+//!
+//! Columns written without a table belong to the previous table. If that table doesn't have
+//! them, we look for them in the other tables of the pine: in `users | orders | s: email`, the
+//! email is the user's. A table with the exact name always beats one where it would only be an
+//! abbreviation.
+use crate::analyze::{ColumnName, Server};
+use crate::engine::query_builder::sql_introspection::Introspective;
+use crate::engine::query_builder::QueryBuildError;
+use crate::engine::syntax::{
+    OptionalInput, Stage4ColumnInput, Stage4ComputationInput, Stage4Condition, Stage4Query,
+    Stage4Selectable, TableInput,
+};
+use crate::engine::{JoinConditions, SelectableHolder, Sourced, UnaryConditionHolder};
+
+type Result<T> = std::result::Result<T, QueryBuildError>;
+
+/// Replaces every abbreviated table and column with its full name.
+pub fn expand_query<'a>(mut query: Stage4Query<'a>, server: &'a Server) -> Result<Stage4Query<'a>> {
+    let mut expander = Expander {
+        server,
+        tables: vec![],
+    };
+
+    // Tables first, columns need to know all of them.
+    expander.table(&mut query.from)?;
+    for join in &mut query.joins {
+        expander.table(&mut join.it.source_table)?;
+        expander.table(&mut join.it.target_table)?;
+    }
+    // Every source is the from or an earlier target, so this is every table of the pine.
+    expander.tables = std::iter::once(query.from)
+        .chain(query.joins.iter().map(|join| join.it.target_table))
+        .collect();
+
+    for join in &mut query.joins {
+        expander.join_conditions(&mut join.it.conditions)?;
+    }
+    for filter in &mut query.filters {
+        expander.condition(&mut filter.it)?;
+    }
+    for selectable in query.selected_columns.iter_mut().chain(&mut query.group_by) {
+        expander.selectable(&mut selectable.it)?;
+    }
+    for order in &mut query.orders {
+        expander.selectable(&mut order.it.selectable.it)?;
+    }
+    for column in &mut query.unselected_columns {
+        expander.column(&mut column.it)?;
+    }
+
+    Ok(query)
+}
+
+/// Used by meta pines like `uP c?`.
+pub fn expand_table<'a>(
+    mut table: Sourced<TableInput<'a>>,
+    server: &'a Server,
+) -> Result<Sourced<TableInput<'a>>> {
+    Expander {
+        server,
+        tables: vec![],
+    }
+    .table(&mut table)?;
+
+    Ok(table)
+}
+
+struct Expander<'a> {
+    server: &'a Server,
+    /// All the tables of the pine, already expanded.
+    tables: Vec<Sourced<TableInput<'a>>>,
+}
+
+impl<'a> Expander<'a> {
+    fn table(&self, table: &mut Sourced<TableInput<'a>>) -> Result<()> {
+        // Unknown databases are reported when building the query.
+        let Ok(names) = self.server.table_names(table.it.database) else {
+            return Ok(());
+        };
+
+        match expand(table.it.table.it.name, &names) {
+            Ok(Some(name)) => table.it.table.it.name = name,
+            Ok(None) => {}
+            Err(candidates) => {
+                return Err(QueryBuildError::AmbiguousTable(
+                    table.map(|t| t.table.it.into()),
+                    candidates,
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    fn column(&self, column: &mut Stage4ColumnInput<'a>) -> Result<()> {
+        self.table(&mut column.table)?;
+
+        // The wildcard is added by us, not typed by the user.
+        if column.column.it.name == "*" {
+            return Ok(());
+        }
+
+        if column.table_is_implicit {
+            self.pick_table(column)?;
+        }
+
+        let Ok(columns) = self.server.columns(column.table) else {
+            return Ok(());
+        };
+        let names: Vec<_> = columns.iter().map(|c| c.name.0.as_str()).collect();
+
+        match expand(column.column.it.name, &names) {
+            Ok(Some(name)) => column.column.it.name = name,
+            Ok(None) => {}
+            Err(candidates) => {
+                return Err(QueryBuildError::AmbiguousColumn(
+                    column.table.map(|t| t.table.it.into()),
+                    column.column.map(|c| ColumnName(c.name.to_string())),
+                    candidates,
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Exact names come first, so `email` doesn't end up meaning another table's `emailSentAt`.
+    /// Abbreviations only count when no table has the exact name.
+    fn pick_table(&self, column: &mut Stage4ColumnInput<'a>) -> Result<()> {
+        let name = column.column.it.name;
+
+        if !self.pick_table_by(column, |table| self.has_column(table, name))? {
+            self.pick_table_by(column, |table| self.abbreviates_column(table, name))?;
+        }
+
+        Ok(())
+    }
+
+    /// The previous table wins, even if other tables match too. Otherwise, exactly one of the
+    /// other tables has to match. Returns whether a table matched.
+    fn pick_table_by(
+        &self,
+        column: &mut Stage4ColumnInput<'a>,
+        matches: impl Fn(Sourced<TableInput>) -> bool,
+    ) -> Result<bool> {
+        if matches(column.table) {
+            return Ok(true);
+        }
+
+        let mut candidates: Vec<Sourced<TableInput>> = Vec::new();
+        for table in &self.tables {
+            let seen = candidates.iter().any(|candidate| candidate.it == table.it);
+
+            if !seen && table.it != column.table.it && matches(*table) {
+                candidates.push(*table);
+            }
+        }
+
+        match candidates.as_slice() {
+            [] => Ok(false),
+            [table] => {
+                column.table = *table;
+
+                Ok(true)
+            }
+            _ => Err(QueryBuildError::ColumnInSeveralTables(
+                column.column.map(|c| ColumnName(c.name.to_string())),
+                candidates
+                    .iter()
+                    .map(|table| table_name(&table.it))
+                    .collect(),
+            )),
+        }
+    }
+
+    fn has_column(&self, table: Sourced<TableInput>, name: &str) -> bool {
+        self.column_names(table)
+            .iter()
+            .any(|column| column.eq_ignore_ascii_case(name))
+    }
+
+    /// Ambiguous abbreviations count too, those are reported once we know the table.
+    fn abbreviates_column(&self, table: Sourced<TableInput>, name: &str) -> bool {
+        expand(name, &self.column_names(table)) != Ok(None)
+    }
+
+    fn column_names(&self, table: Sourced<TableInput>) -> Vec<&'a str> {
+        match self.server.columns(table) {
+            Ok(columns) => columns.iter().map(|c| c.name.0.as_str()).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn join_conditions(&self, conditions: &mut JoinConditions<Stage4Condition<'a>>) -> Result<()> {
+        if let JoinConditions::Explicit(conditions) = conditions {
+            for condition in conditions {
+                self.condition(&mut condition.it)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn selectable(&self, selectable: &mut Stage4Selectable<'a>) -> Result<()> {
+        match selectable {
+            SelectableHolder::Condition(condition) => self.condition(&mut condition.it),
+            SelectableHolder::Computation(computation) => self.computation(&mut computation.it),
+        }
+    }
+
+    fn condition(&self, condition: &mut Stage4Condition<'a>) -> Result<()> {
+        match condition {
+            Stage4Condition::ImplicitId(table, _) => self.table(table),
+            Stage4Condition::Binary(condition) => {
+                self.computation(&mut condition.it.left.it)?;
+                self.computation(&mut condition.it.right.it)
+            }
+            Stage4Condition::Unary(condition) => match &mut condition.it {
+                UnaryConditionHolder::IsNull(computation)
+                | UnaryConditionHolder::IsNotNull(computation) => {
+                    self.computation(&mut computation.it)
+                }
+            },
+        }
+    }
+
+    fn computation(&self, computation: &mut Stage4ComputationInput<'a>) -> Result<()> {
+        match computation {
+            Stage4ComputationInput::Column(column) => self.column(&mut column.it),
+            Stage4ComputationInput::FunctionCall(fn_call) => {
+                for param in &mut fn_call.it.params {
+                    self.computation(&mut param.it)?;
+                }
+
+                Ok(())
+            }
+            Stage4ComputationInput::Value(_) => Ok(()),
+        }
+    }
+}
+
+/// How tables are written in pines, like `users` or `auth.users`.
+fn table_name(table: &TableInput) -> String {
+    match table.database {
+        OptionalInput::Specified(database) => {
+            format!("{}.{}", database.it.name, table.table.it.name)
+        }
+        OptionalInput::Implicit => table.table.it.name.to_string(),
+    }
+}
+
+/// The full name for an abbreviation, or `None` when the name is already complete or matches
+/// nothing. Fails with the sorted candidates when there's more than one.
+fn expand<'a>(
+    abbreviation: &str,
+    names: &[&'a str],
+) -> std::result::Result<Option<&'a str>, Vec<String>> {
+    if names.contains(&abbreviation) {
+        return Ok(None);
+    }
+
+    let mut candidates: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| is_abbreviation(abbreviation, name))
+        .collect();
+
+    // `Users` means `users`, even if `usersArchive` exists too.
+    if candidates
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(abbreviation))
+    {
+        candidates.retain(|name| name.eq_ignore_ascii_case(abbreviation));
+    }
+
+    match candidates.as_slice() {
+        [] => Ok(None),
+        [name] => Ok(Some(name)),
+        _ => {
+            candidates.sort();
+
+            Err(candidates.into_iter().map(String::from).collect())
+        }
+    }
+}
+
+fn is_abbreviation(abbreviation: &str, name: &str) -> bool {
+    if name
+        .to_lowercase()
+        .starts_with(&abbreviation.to_lowercase())
+    {
+        return true;
+    }
+
+    let abbreviation_words = words(abbreviation);
+    let name_words = words(name);
+
+    abbreviation_words.len() <= name_words.len()
+        && abbreviation_words
+            .iter()
+            .zip(&name_words)
+            .all(|(start, word)| word.to_lowercase().starts_with(&start.to_lowercase()))
+}
+
+/// Splits camelCase and snake_case names: `userId` and `user_id` both give `["user", "Id"]`
+/// (or `"id"`).
+fn words(name: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+
+    for (position, char) in name.char_indices() {
+        if char == '_' {
+            words.push(&name[start..position]);
+            start = position + 1;
+        } else if char.is_uppercase()
+            && previous.is_some_and(|previous| previous.is_lowercase() || previous.is_numeric())
+        {
+            words.push(&name[start..position]);
+            start = position;
+        }
+
+        previous = Some(char);
+    }
+    words.push(&name[start..]);
+
+    words.retain(|word| !word.is_empty());
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::sql::fixtures::{server, table};
+    use crate::engine::syntax::{parse_to_stage4, Stage4Rep};
+
+    fn expand_pine(input: &str, server: &Server) -> Result<()> {
+        match parse_to_stage4(input).unwrap() {
+            Stage4Rep::Query(query) => expand_query(query, server).map(|_| ()),
+            _ => panic!("this must be a query"),
+        }
+    }
+
+    /// The tables the selected columns ended up in.
+    fn select_tables<'a>(input: &'a str, server: &'a Server) -> Vec<&'a str> {
+        let query = match parse_to_stage4(input).unwrap() {
+            Stage4Rep::Query(query) => expand_query(query, server).unwrap(),
+            _ => panic!("this must be a query"),
+        };
+
+        query
+            .selected_columns
+            .iter()
+            .map(|selectable| match &selectable.it {
+                SelectableHolder::Computation(computation) => match &computation.it {
+                    Stage4ComputationInput::Column(column) => column.it.table.it.table.it.name,
+                    _ => panic!("only columns are selected"),
+                },
+                _ => panic!("only columns are selected"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_columns_of_other_tables() {
+        let server = server(vec![(
+            "default",
+            vec![
+                table("users", &["id", "email", "status"]),
+                table("orders", &["id", "userId", "status"]),
+                table("payments", &["id", "orderId"]),
+            ],
+        )]);
+
+        assert_eq!(
+            select_tables("users | orders | payments | s: id em userId", &server),
+            ["payments", "users", "orders"]
+        );
+        // The previous table wins.
+        assert_eq!(
+            select_tables("users | orders | s: status", &server),
+            ["orders"]
+        );
+
+        match expand_pine("users | orders | payments | s: status", &server) {
+            Err(QueryBuildError::ColumnInSeveralTables(column, tables)) => {
+                assert_eq!(column.it.0, "status");
+                assert_eq!(tables, ["users", "orders"]);
+            }
+            Err(error) => panic!("unexpected error: {error}"),
+            Ok(_) => panic!("status is in several tables"),
+        }
+    }
+
+    #[test]
+    fn test_exact_columns_before_abbreviations() {
+        let server = server(vec![(
+            "default",
+            vec![
+                table("users", &["id", "email", "status"]),
+                table(
+                    "orders",
+                    &["id", "userId", "emailSentAt", "statusA", "statusB"],
+                ),
+            ],
+        )]);
+
+        assert_eq!(
+            select_tables("users | orders | s: email", &server),
+            ["users"]
+        );
+        // Ambiguous in orders, but users has it.
+        assert_eq!(
+            select_tables("users | orders | s: status", &server),
+            ["users"]
+        );
+        assert_eq!(
+            select_tables("users | orders | s: emSe", &server),
+            ["orders"]
+        );
+    }
+
+    #[test]
+    fn test_columns_of_explicitly_joined_tables() {
+        let server = server(vec![(
+            "default",
+            vec![
+                table("users", &["id", "email"]),
+                table("orders", &["id", "userId", "total"]),
+                table("payments", &["id", "orderId"]),
+            ],
+        )]);
+
+        assert_eq!(
+            select_tables(
+                "users | j: orders id = userId | j: payments id = orderId | s: em userId orderId",
+                &server
+            ),
+            ["users", "orders", "payments"]
+        );
+        // Tables joined after the filter count as well.
+        let query = match parse_to_stage4("users | w: total > 1 | j: orders id = userId").unwrap() {
+            Stage4Rep::Query(query) => expand_query(query, &server).unwrap(),
+            _ => panic!("this must be a query"),
+        };
+        match &query.filters[0].it {
+            Stage4Condition::Binary(condition) => match &condition.it.left.it {
+                Stage4ComputationInput::Column(column) => {
+                    assert_eq!(column.it.table.it.table.it.name, "orders")
+                }
+                _ => panic!("the filter starts with a column"),
+            },
+            _ => panic!("the filter is a comparison"),
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        let tables = [
+            "users",
+            "userPreferences",
+            "orders",
+            "order_items",
+            "created_at",
+        ];
+
+        assert_eq!(expand("users", &tables), Ok(None));
+        assert_eq!(expand("uP", &tables), Ok(Some("userPreferences")));
+        assert_eq!(expand("userPr", &tables), Ok(Some("userPreferences")));
+        assert_eq!(expand("Users", &tables), Ok(Some("users")));
+        assert_eq!(
+            expand("user", &tables),
+            Err(vec!["userPreferences".to_string(), "users".to_string()])
+        );
+        assert_eq!(expand("oI", &tables), Ok(Some("order_items")));
+        assert_eq!(expand("crAt", &tables), Ok(Some("created_at")));
+        assert_eq!(
+            expand("ord", &tables),
+            Err(vec!["order_items".to_string(), "orders".to_string()])
+        );
+        assert_eq!(expand("payments", &tables), Ok(None));
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(words("userPreferences"), ["user", "Preferences"]);
+        assert_eq!(words("user_id"), ["user", "id"]);
+        assert_eq!(words("address2Line"), ["address2", "Line"]);
+        assert_eq!(words("ID"), ["ID"]);
+    }
+}
@@ -1,27 +1,46 @@
 use crate::analyze::{
-    Column, ColumnName, DBType, Database, DatabaseName, ForeignKey, Key, KeyReference, Server,
-    Table, TableName,
+    Column, ColumnName, Database, DatabaseName, ForeignKey, ForeignKeyKind, Index, Key,
+    KeyReference, Server, Table, TableKind, TableName,
 };
+use crate::engine::query_builder::suggestions::closest_names;
 use crate::engine::query_builder::{
-    BinaryCondition, Computation, Condition, QueryBuildError, SelectedColumn, Sourced,
+    BinaryCondition, Computation, Condition, Neighbor, NeighborDirection, QueryBuildError,
+    SelectedColumn, Sourced,
 };
 use crate::engine::syntax::{OptionalInput, SqlIdentifierInput, TableInput};
 use crate::engine::Comparison;
 use log::info;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
 type Result<T> = std::result::Result<T, QueryBuildError>;
+type TableNode = (DatabaseName, TableName);
+type NeighborEdge = (NeighborDirection, ForeignKey, TableNode);
 
 pub trait Introspective {
+    /// Also returns the kind of foreign key the join is based on, so we can tell the user when
+    /// we're relying on guesswork.
     fn join_conditions(
         &self,
         from: Sourced<TableInput>,
         to: Sourced<TableInput>,
-    ) -> Result<Vec<Sourced<Condition>>>;
+    ) -> Result<(Vec<Sourced<Condition>>, ForeignKeyKind)>;
     fn columns(&self, table: Sourced<TableInput>) -> Result<&[Column]>;
     fn neighbors(&self, table: Sourced<TableInput>) -> Result<Vec<ForeignKey>>;
-    fn primary_key(&self, table: Sourced<TableInput>) -> Result<&Key>;
+    /// Tables up to `depth` joins away. Each table shows up under the first table that reaches
+    /// it in the fewest joins, so the pine leading to it is as short as possible.
+    fn neighbor_tree(&self, table: Sourced<TableInput>, depth: usize) -> Result<Vec<Neighbor>>;
+    /// Views and some other tables might not have primary keys.
+    fn primary_key(&self, table: Sourced<TableInput>) -> Result<Option<&Key>>;
+    fn table_kind(&self, table: Sourced<TableInput>) -> Result<TableKind>;
+    fn indexes(&self, table: Sourced<TableInput>) -> Result<&[Index]>;
+    /// Tables of the default database with names containing the filter, ignoring case. Sorted
+    /// by name.
+    fn tables(&self, filter: Option<&str>) -> Result<(&Database, Vec<&Table>)>;
+    fn table_names(
+        &self,
+        database: OptionalInput<Sourced<SqlIdentifierInput>>,
+    ) -> Result<Vec<&str>>;
 }
 
 impl Introspective for Server {
@@ -29,7 +48,7 @@ impl Introspective for Server {
         &self,
         from: Sourced<TableInput>,
         to: Sourced<TableInput>,
-    ) -> Result<Vec<Sourced<Condition>>> {
+    ) -> Result<(Vec<Sourced<Condition>>, ForeignKeyKind)> {
         let join = self.find_join(from, to)?;
 
         if join.from.key.columns.len() != join.to.key.columns.len() {
@@ -55,7 +74,7 @@ impl Introspective for Server {
             .map(Sourced::from_introspection)
             .collect();
 
-        Ok(conditions)
+        Ok((conditions, join.kind))
     }
 
     fn columns(&self, table: Sourced<TableInput>) -> Result<&[Column]> {
@@ -65,37 +84,172 @@ impl Introspective for Server {
     }
 
     fn neighbors(&self, table: Sourced<TableInput>) -> Result<Vec<ForeignKey>> {
-        info!("searching for direct joins");
-        let direct_joins = self.table(table)?.foreign_keys.iter().cloned();
-        info!("searching for reverse joins");
-        let reverse_joins = self
-            .database_or_default(table.it.database)?
-            .tables
-            .iter()
-            .flat_map(|(_, other)| {
-                other
-                    .foreign_keys
-                    .iter()
-                    .find(|other_table| other_table.to.table == table.it.table.it)
-            })
-            .map(|fk| fk.invert());
+        let database = &self.database_or_default(table.it.database)?.name;
+        let table = &self.table(table)?.name;
+
+        Ok(self
+            .directed_neighbors(database, table)
+            .into_iter()
+            .map(|(_, fk)| fk)
+            .collect())
+    }
 
-        let mut all_joins: Vec<_> = direct_joins.chain(reverse_joins).collect();
+    fn neighbor_tree(&self, table: Sourced<TableInput>, depth: usize) -> Result<Vec<Neighbor>> {
+        let default_database = &self.default_database()?.name;
+        let root = (
+            self.database_or_default(table.it.database)?.name.clone(),
+            self.table(table)?.name.clone(),
+        );
+
+        // Breadth first, so every table is claimed by a parent as close to the root as possible.
+        let mut visited = HashSet::from([root.clone()]);
+        let mut edges: HashMap<TableNode, Vec<NeighborEdge>> = HashMap::new();
+        let mut frontier = vec![root.clone()];
+
+        for _ in 0..depth {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let mut next = Vec::new();
+
+            for parent in &frontier {
+                let mut claimed = Vec::new();
+
+                for (direction, fk) in self.directed_neighbors(&parent.0, &parent.1) {
+                    let target = (fk.to.database_or(&parent.0).clone(), fk.to.table.clone());
+
+                    // Several keys can join the same two tables, we list all of them.
+                    if visited.insert(target.clone()) {
+                        claimed.push(target.clone());
+                        next.push(target.clone());
+                    } else if !claimed.contains(&target) {
+                        continue;
+                    }
+
+                    edges
+                        .entry(parent.clone())
+                        .or_default()
+                        .push((direction, fk, target));
+                }
+            }
 
-        info!("deduplicating joins");
-        all_joins.dedup_by(|a, b| a == b);
+            frontier = next;
+        }
 
-        Ok(all_joins)
+        Ok(build_neighbor_tree(&root, &mut edges, default_database))
     }
 
-    fn primary_key(&self, table: Sourced<TableInput>) -> Result<&Key> {
+    fn primary_key(&self, table: Sourced<TableInput>) -> Result<Option<&Key>> {
         let table = self.table(table)?;
 
-        Ok(&table.primary_key)
+        Ok(table.primary_key.as_ref())
+    }
+
+    fn table_kind(&self, table: Sourced<TableInput>) -> Result<TableKind> {
+        let table = self.table(table)?;
+
+        Ok(table.kind)
+    }
+
+    fn indexes(&self, table: Sourced<TableInput>) -> Result<&[Index]> {
+        let table = self.table(table)?;
+
+        Ok(table.indexes.as_slice())
+    }
+
+    fn table_names(
+        &self,
+        database: OptionalInput<Sourced<SqlIdentifierInput>>,
+    ) -> Result<Vec<&str>> {
+        let database = self.database_or_default(database)?;
+
+        Ok(database
+            .tables
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect())
+    }
+
+    fn tables(&self, filter: Option<&str>) -> Result<(&Database, Vec<&Table>)> {
+        let database = self.default_database()?;
+        let filter = filter.map(str::to_lowercase);
+
+        let mut tables: Vec<_> = database
+            .tables
+            .iter()
+            .map(|(_, table)| table)
+            .filter(|table| match &filter {
+                Some(filter) => table.name.as_str().to_lowercase().contains(filter),
+                None => true,
+            })
+            .collect();
+        tables.sort_by(|a, b| a.name.as_str().cmp(b.name.as_str()));
+
+        Ok((database, tables))
     }
 }
 
+fn build_neighbor_tree(
+    parent: &TableNode,
+    edges: &mut HashMap<TableNode, Vec<NeighborEdge>>,
+    default_database: &DatabaseName,
+) -> Vec<Neighbor> {
+    // Removing the edges means a table joined by several keys only gets its neighbors listed
+    // under the first one.
+    edges
+        .remove(parent)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(direction, foreign_key, target)| Neighbor {
+            direction,
+            foreign_key,
+            database: Some(target.0.clone()).filter(|database| database != default_database),
+            children: build_neighbor_tree(&target, edges, default_database),
+        })
+        .collect()
+}
+
 impl Server {
+    /// Keys declared by the table, followed by the keys pointing to it. The latter are inverted,
+    /// so all keys start from the table.
+    fn directed_neighbors(
+        &self,
+        database: &DatabaseName,
+        table: &TableName,
+    ) -> Vec<(NeighborDirection, ForeignKey)> {
+        let graph = self.foreign_key_graph();
+
+        info!("searching for direct joins");
+        let direct_joins = graph
+            .outgoing(database, table)
+            .iter()
+            .map(|fk| (NeighborDirection::Outgoing, fk.clone()));
+        info!("searching for reverse joins");
+        // Tables from other databases can point to this one too.
+        let reverse_joins = graph
+            .incoming(database, table)
+            .iter()
+            .map(|(other_database, fk)| {
+                // The inverted key belongs to this table now, so the databases have to be
+                // relative to this table's database.
+                let mut inverted = fk.invert();
+                inverted.from.database = None;
+                inverted.to.database = Some(other_database.clone())
+                    .filter(|other_database| other_database != database);
+
+                (NeighborDirection::Incoming, inverted)
+            });
+
+        info!("deduplicating joins");
+        let mut seen = HashSet::new();
+
+        direct_joins
+            .chain(reverse_joins)
+            .filter(|(_, join)| seen.insert(join.clone()))
+            .collect()
+    }
+
     fn find_join(&self, from: Sourced<TableInput>, to: Sourced<TableInput>) -> Result<ForeignKey> {
         if let Some(direct_join) = self.find_direct_join(from, to)? {
             return Ok(direct_join.clone());
@@ -123,11 +277,17 @@ impl Server {
         from: Sourced<TableInput>,
         to: Sourced<TableInput>,
     ) -> Result<Option<&ForeignKey>> {
+        let from_database = &self.database_or_default(from.it.database)?.name;
+        let to_database = &self.database_or_default(to.it.database)?.name;
+        let from_table = &self.table(from)?.name;
+
         let mut matching_keys = self
-            .table(from)?
-            .foreign_keys
+            .foreign_key_graph()
+            .outgoing(from_database, from_table)
             .iter()
-            .filter(|fk| fk.to.table == to.it.table.it);
+            .filter(|fk| {
+                fk.to.table == to.it.table.it && fk.to.database_or(from_database) == to_database
+            });
 
         // auto joins get the first possible way to join, even if multiple are available
         Ok(matching_keys.next())
@@ -145,29 +305,40 @@ impl Server {
         from: Sourced<TableInput>,
         to: Sourced<TableInput>,
     ) -> Result<Option<ForeignKey>> {
+        let from_database = &self.database_or_default(from.it.database)?.name;
+        let to_database = &self.database_or_default(to.it.database)?.name;
         let from = self.table(from)?;
         let to = self.table(to)?;
 
-        let mut first_common = from
-            .foreign_keys
+        let graph = self.foreign_key_graph();
+
+        // The order of the columns is not relevant when comparing keys.
+        let target = |fk: &ForeignKey, owner_database| {
+            let mut columns = fk.to.key.columns.clone();
+            columns.sort();
+
+            (
+                fk.to.database_or(owner_database).clone(),
+                fk.to.table.clone(),
+                columns,
+            )
+        };
+
+        // Only the first key wins, auto joins don't care about the others.
+        let mut to_keys_by_target = HashMap::new();
+        for to_fk in graph.outgoing(to_database, &to.name) {
+            to_keys_by_target
+                .entry(target(to_fk, to_database))
+                .or_insert(to_fk);
+        }
+
+        let mut first_common = graph
+            .outgoing(from_database, &from.name)
             .iter()
             .filter_map(|from_fk| {
-                // O(n^2) is not the best, but the numbers should be low.
-                to.foreign_keys
-                    .iter()
-                    .find(|to_fk| {
-                        if from_fk.to.table != to_fk.to.table {
-                            return false;
-                        }
-
-                        // We use a hash for comparison here because in this case the order of
-                        // keys is not relevant.
-                        let from_keys: HashSet<_> = from_fk.to.key.columns.iter().collect();
-                        let to_keys: HashSet<_> = to_fk.to.key.columns.iter().collect();
-
-                        from_keys == to_keys
-                    })
-                    .map(|to_fk| (from_fk, to_fk))
+                to_keys_by_target
+                    .get(&target(from_fk, from_database))
+                    .map(|&to_fk| (from_fk, to_fk))
             })
             .map(|(from_fk, to_fk)| {
                 // Our foreign key could look something like this:
@@ -203,17 +374,25 @@ impl Server {
                 // Yes, this is a fake foreign key.
                 ForeignKey {
                     from: KeyReference {
+                        database: None,
                         table: from.name.clone(),
                         key: Key {
                             columns: from_key_columns,
                         },
                     },
                     to: KeyReference {
+                        database: None,
                         table: to.name.clone(),
                         key: Key {
                             columns: to_key_columns,
                         },
                     },
+                    // A join is only as trustworthy as the least trustworthy key it's based on.
+                    kind: if from_fk.kind == ForeignKeyKind::Declared {
+                        to_fk.kind
+                    } else {
+                        from_fk.kind
+                    },
                 }
             });
 
@@ -228,25 +407,25 @@ impl Server {
         };
 
         let table_name = TableName::new(name.it.table.it.name.to_string());
-        let table = database
-            .tables
-            .get(&table_name)
-            .ok_or_else(|| QueryBuildError::TableNotFound(name.map(|_| table_name)))?;
+        let table = database.tables.get(&table_name).ok_or_else(|| {
+            let suggestions = closest_names(
+                table_name.as_str(),
+                database.tables.iter().map(|(name, _)| name.as_str()),
+            );
+
+            QueryBuildError::TableNotFound(name.map(|_| table_name.clone()), suggestions)
+        })?;
 
         Ok(table)
     }
 
     fn default_database(&self) -> Result<&Database> {
-        let db_or_schema = match self.params.db_type {
-            DBType::PostgresSQL => self
-                .params
-                .default_schema
-                .as_ref()
-                .ok_or(QueryBuildError::InvalidPostgresConfig)?,
-            DBType::MariaDB => &self.params.database,
-        };
+        let db_or_schema = self
+            .params
+            .default_database()
+            .ok_or(QueryBuildError::InvalidPostgresConfig)?;
 
-        self.databases
+        self.databases()
             .get(db_or_schema)
             .ok_or_else(|| QueryBuildError::DefaultDatabaseNotFound(self.params.clone()))
     }
@@ -264,9 +443,12 @@ impl Server {
     fn database<T: AsRef<str> + Clone + Debug>(&self, name: Sourced<T>) -> Result<&Database> {
         let db_name = DatabaseName(name.it.as_ref().to_string());
 
-        self.databases
-            .get(&db_name)
-            .ok_or_else(|| QueryBuildError::DatabaseNotFound(name.map(|_| db_name)))
+        self.databases().get(&db_name).ok_or_else(|| {
+            let known_databases = self.databases().keys().chain(&self.unloaded_databases);
+            let suggestions = closest_names(&db_name.0, known_databases.map(|db| db.0.as_str()));
+
+            QueryBuildError::DatabaseNotFound(name.map(|_| db_name.clone()), suggestions)
+        })
     }
 }
 
@@ -293,3 +475,71 @@ where
         .map(|col| (*col).clone())
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::analyze::{Server, Table};
+    use crate::engine::sql::fixtures::{foreign_key, table};
+
+    /// `billing.invoices.userId` points to `auth.users.id`.
+    fn server() -> Server {
+        let invoices = Table {
+            foreign_keys: vec![foreign_key("invoices.userId", "auth.users.id")],
+            ..table("invoices", &["id", "userId"])
+        };
+
+        crate::engine::sql::fixtures::server(vec![
+            ("billing", vec![invoices]),
+            ("auth", vec![table("users", &["id"])]),
+        ])
+    }
+
+    #[test]
+    fn test_join_across_databases() {
+        let server = server();
+
+        let query = crate::render("billing.invoices | auth.users", &server).unwrap();
+        assert!(
+            query.contains("LEFT JOIN billing.invoices ON billing.invoices.userId = auth.users.id"),
+            "{query}"
+        );
+
+        let neighbors = crate::render("auth.users |", &server).unwrap();
+        assert!(
+            neighbors.contains("billing.invoices.userId using .id"),
+            "{neighbors}"
+        );
+    }
+
+    #[test]
+    fn test_neighbor_tree_across_databases() {
+        let server = server();
+
+        // billing is the default database, so its tables don't need a prefix.
+        let tree = crate::render("auth.users |99999999999", &server).unwrap();
+        assert_eq!(
+            tree,
+            "/*\n\
+             Neighbors of `auth.users`, up to 99999999999 joins away:\n  \
+               incoming invoices.userId -> auth.users.id  auth.users | invoices\n\
+             */--"
+        );
+    }
+
+    #[test]
+    fn test_suggest_names() {
+        let mut server = server();
+        server.unloaded_databases = vec!["reports".into()];
+
+        let render_error = |input| crate::render(input, &server).unwrap_err().to_string();
+
+        let error = render_error("invoices | auth.usres");
+        assert!(error.contains("did you mean `users`?"), "{error}");
+
+        let error = render_error("invoices | report.daily");
+        assert!(error.contains("did you mean `reports`?"), "{error}");
+
+        let error = render_error("invoices | payments");
+        assert!(!error.contains("did you mean"), "{error}");
+    }
+}
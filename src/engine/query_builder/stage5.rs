@@ -1,8 +1,9 @@
-use crate::analyze::{Column, ColumnName, DatabaseName, Server};
+use crate::analyze::{Column, ColumnName, DBType, DatabaseName, Server, TableKind};
 use crate::engine::query_builder::sql_introspection::Introspective;
+use crate::engine::query_builder::suggestions::closest_names;
 use crate::engine::query_builder::{
     BinaryCondition, Computation, Condition, ExplicitJoin, FunctionCall, LiteralValue, Query,
-    Selectable, SelectedColumn, Table, UnaryCondition,
+    QueryWarning, Selectable, SelectedColumn, Table, UnaryCondition,
 };
 use crate::engine::syntax::{
     OptionalInput, Stage4BinaryCondition, Stage4ColumnInput, Stage4ComputationInput,
@@ -11,7 +12,7 @@ use crate::engine::syntax::{
 };
 use crate::engine::{
     Comparison, JoinConditions, LimitHolder, LiteralValueHolder, OrderHolder, QueryBuildError,
-    SelectableHolder, Sourced,
+    SelectableHolder, Sourced, UnaryConditionHolder,
 };
 use log::info;
 use std::fmt::Debug;
@@ -34,6 +35,8 @@ impl<'a> Stage5Builder<'a> {
     }
 
     pub fn try_build(self) -> Result<Query, QueryBuildError> {
+        info!("validating columns");
+        self.validate_columns()?;
         info!("processing selects");
         let select = self.process_selects()?;
         info!("processing unselects");
@@ -46,6 +49,8 @@ impl<'a> Stage5Builder<'a> {
         let orders = self.process_orders()?;
         info!("processing group by");
         let group_by = self.process_group_by()?;
+        info!("looking for unindexed columns");
+        let warnings = self.find_unindexed_columns();
 
         info!("processing from");
 
@@ -63,6 +68,7 @@ impl<'a> Stage5Builder<'a> {
             orders,
             group_by,
             limit: self.input.limit.map(|limit| limit.into()),
+            warnings,
         })
     }
 
@@ -159,11 +165,15 @@ impl<'a> Stage5Builder<'a> {
     }
 
     fn process_join(&self, join: &Stage4Join) -> Result<ExplicitJoin, QueryBuildError> {
-        let conditions = match &join.conditions {
-            JoinConditions::Auto => self
-                .server
-                .join_conditions(join.source_table, join.target_table)?,
-            JoinConditions::Explicit(conditions) => self.process_conditions(conditions)?,
+        let (conditions, key_kind) = match &join.conditions {
+            JoinConditions::Auto => {
+                let (conditions, kind) = self
+                    .server
+                    .join_conditions(join.source_table, join.target_table)?;
+
+                (conditions, Some(kind))
+            }
+            JoinConditions::Explicit(conditions) => (self.process_conditions(conditions)?, None),
         };
 
         Ok(ExplicitJoin {
@@ -177,6 +187,7 @@ impl<'a> Stage5Builder<'a> {
             // This is just a design decision I made.
             target_table: join.source_table.into(),
             conditions,
+            key_kind,
         })
     }
 
@@ -250,7 +261,16 @@ impl<'a> Stage5Builder<'a> {
         id_value: Sourced<Stage4LiteralValue>,
     ) -> Result<BinaryCondition, QueryBuildError> {
         let column_name = {
-            let primary_key = self.server.primary_key(table_name)?;
+            let primary_key = match self.server.primary_key(table_name)? {
+                Some(primary_key) => primary_key,
+                None => {
+                    return Err(QueryBuildError::MissingPrimaryKey(
+                        table_name.map(|t| t.table.it.into()),
+                        self.server.table_kind(table_name)?,
+                        id_value.into(),
+                    ))
+                }
+            };
 
             if primary_key.columns.len() != 1 {
                 return Err(QueryBuildError::InvalidImplicitIdCondition(
@@ -360,11 +380,194 @@ impl<'a> Stage5Builder<'a> {
         )))
     }
 
+    /// Makes sure the columns the user typed exist, so typos are caught before the query gets to
+    /// the server.
+    fn validate_columns(&self) -> Result<(), QueryBuildError> {
+        let mut columns = Vec::new();
+
+        for selectable in self
+            .input
+            .selected_columns
+            .iter()
+            .chain(&self.input.group_by)
+        {
+            selectable_columns(&selectable.it, &mut columns);
+        }
+        for order in &self.input.orders {
+            selectable_columns(&order.it.selectable.it, &mut columns);
+        }
+
+        let join_conditions = self
+            .input
+            .joins
+            .iter()
+            .filter_map(|join| match &join.it.conditions {
+                JoinConditions::Explicit(conditions) => Some(conditions),
+                JoinConditions::Auto => None,
+            })
+            .flatten();
+        for condition in self.input.filters.iter().chain(join_conditions) {
+            condition_columns(&condition.it, &mut columns);
+        }
+
+        columns
+            .into_iter()
+            .try_for_each(|column| self.validate_column(column))
+    }
+
+    fn validate_column(&self, column: Stage4ColumnInput) -> Result<(), QueryBuildError> {
+        let name = column.column.it.name;
+        // The wildcard is added by us, not typed by the user.
+        if name == "*" {
+            return Ok(());
+        }
+
+        // Tables missing from the analysis still get rendered, they might be new. We just can't
+        // tell anything about their columns.
+        let Ok(columns) = self.server.columns(column.table) else {
+            return Ok(());
+        };
+        if columns.iter().any(|c| self.is_named(c, name)) {
+            return Ok(());
+        }
+
+        Err(QueryBuildError::ColumnNotFound(
+            column.table.map(|t| t.table.it.into()),
+            column.column.map(|c| ColumnName(c.name.to_string())),
+            closest_names(name, columns.iter().map(|c| c.name.0.as_str())),
+        ))
+    }
+
+    /// Only plain columns are checked: wrapping a column in a function call prevents the use of an
+    /// index anyway.
+    fn find_unindexed_columns(&self) -> Vec<QueryWarning> {
+        let mut columns: Vec<Stage4ColumnInput> = Vec::new();
+
+        for filter in &self.input.filters {
+            match &filter.it {
+                Stage4Condition::ImplicitId(_, _) => {} // always uses the primary key
+                Stage4Condition::Binary(condition) => {
+                    columns.extend(plain_column(&condition.it.left.it));
+                    columns.extend(plain_column(&condition.it.right.it));
+                }
+                Stage4Condition::Unary(condition) => match &condition.it {
+                    UnaryConditionHolder::IsNull(computation)
+                    | UnaryConditionHolder::IsNotNull(computation) => {
+                        columns.extend(plain_column(&computation.it));
+                    }
+                },
+            }
+        }
+
+        for order in &self.input.orders {
+            if let SelectableHolder::Computation(computation) = &order.it.selectable.it {
+                columns.extend(plain_column(&computation.it));
+            }
+        }
+
+        let mut warnings: Vec<QueryWarning> = Vec::new();
+        for column in columns.into_iter().filter(|c| self.is_unindexed(c)) {
+            let warning =
+                QueryWarning::UnindexedColumn(column.table.it.into(), column.column.it.name.into());
+
+            if !warnings.contains(&warning) {
+                warnings.push(warning);
+            }
+        }
+
+        warnings
+    }
+
+    /// Unknown tables and columns are not our concern here, so they're never "unindexed". Views
+    /// can't have indexes, and tables without any were either analyzed before we recorded them
+    /// or are small enough not to need them.
+    fn is_unindexed(&self, column: &Stage4ColumnInput) -> bool {
+        let name = column.column.it.name;
+        // Indexes list the column the way the table names it, not the way it was typed.
+        let Some(known) = self
+            .server
+            .columns(column.table)
+            .ok()
+            .and_then(|columns| columns.iter().find(|c| self.is_named(c, name)))
+        else {
+            return false;
+        };
+        let is_view = self
+            .server
+            .table_kind(column.table)
+            .is_ok_and(|kind| kind == TableKind::View);
+
+        !is_view
+            && self.server.indexes(column.table).is_ok_and(|indexes| {
+                !indexes.is_empty() && !indexes.iter().any(|index| index.covers(&known.name.0))
+            })
+    }
+
+    /// MariaDB doesn't care about the case of column names, but quoted names on Postgres do.
+    fn is_named(&self, column: &Column, name: &str) -> bool {
+        match self.server.params.db_type {
+            DBType::MariaDB => column.name.0.eq_ignore_ascii_case(name),
+            DBType::PostgresSQL => column.name.0 == name,
+        }
+    }
+
     fn is_single_table_query(&self) -> bool {
         self.input.joins.is_empty()
     }
 }
 
+fn plain_column<'a>(computation: &Stage4ComputationInput<'a>) -> Option<Stage4ColumnInput<'a>> {
+    match computation {
+        Stage4ComputationInput::Column(column) => Some(column.it),
+        _ => None,
+    }
+}
+
+fn selectable_columns<'a>(
+    selectable: &Stage4Selectable<'a>,
+    columns: &mut Vec<Stage4ColumnInput<'a>>,
+) {
+    match selectable {
+        SelectableHolder::Condition(condition) => condition_columns(&condition.it, columns),
+        SelectableHolder::Computation(computation) => computation_columns(&computation.it, columns),
+    }
+}
+
+fn condition_columns<'a>(
+    condition: &Stage4Condition<'a>,
+    columns: &mut Vec<Stage4ColumnInput<'a>>,
+) {
+    match condition {
+        // The primary key comes from the analysis, it's always there.
+        Stage4Condition::ImplicitId(_, _) => {}
+        Stage4Condition::Binary(condition) => {
+            computation_columns(&condition.it.left.it, columns);
+            computation_columns(&condition.it.right.it, columns);
+        }
+        Stage4Condition::Unary(condition) => match &condition.it {
+            UnaryConditionHolder::IsNull(computation)
+            | UnaryConditionHolder::IsNotNull(computation) => {
+                computation_columns(&computation.it, columns)
+            }
+        },
+    }
+}
+
+fn computation_columns<'a>(
+    computation: &Stage4ComputationInput<'a>,
+    columns: &mut Vec<Stage4ColumnInput<'a>>,
+) {
+    match computation {
+        Stage4ComputationInput::Column(column) => columns.push(column.it),
+        Stage4ComputationInput::FunctionCall(fn_call) => {
+            for param in &fn_call.it.params {
+                computation_columns(&param.it, columns);
+            }
+        }
+        Stage4ComputationInput::Value(_) => {}
+    }
+}
+
 fn unselect_matches_wildcard(unselect: &Stage4ColumnInput, select: &SelectedColumn) -> bool {
     if select.column.it.0 != "*" {
         // hardcoded wildcard char, oh yeaaah!
@@ -519,23 +722,23 @@ impl PartialEq<Option<Sourced<Table>>> for Sourced<TableInput<'_>> {
 
 #[cfg(test)]
 mod test {
-    use crate::analyze::{DBType, Server, ServerParams};
+    use crate::analyze::{DBType, Index, Server, Table, TableKind};
     use crate::engine::query_builder::stage5::Stage5Builder;
+    use crate::engine::query_builder::Query;
+    use crate::engine::sql::fixtures::{server, table};
     use crate::engine::syntax::{parse_to_stage4, Stage4Rep};
+    use crate::engine::QueryBuildError;
+
+    fn build(input: &str, server: &Server) -> Result<Query, QueryBuildError> {
+        match parse_to_stage4(input).unwrap() {
+            Stage4Rep::Query(query) => Stage5Builder::new(query, server).try_build(),
+            _ => panic!("this must be a query"),
+        }
+    }
 
     #[test]
     fn test_try_from_simple() {
-        let server = Server {
-            params: ServerParams {
-                db_type: DBType::MariaDB,
-                hostname: "".to_string(),
-                port: 0,
-                user: "".to_string(),
-                database: "".into(),
-                default_schema: None,
-            },
-            databases: Default::default(),
-        };
+        let server = server(vec![]);
 
         let stage4 = parse_to_stage4("table | s: id").unwrap();
 
@@ -552,4 +755,137 @@ mod test {
 
         assert_eq!(query.from.it.name.it, "table");
     }
+
+    #[test]
+    fn test_implicit_id_without_primary_key() {
+        let view = Table {
+            kind: TableKind::View,
+            ..table("activeUsers", &["id"])
+        };
+        let server = server(vec![("default", vec![view])]);
+
+        let stage4 = parse_to_stage4("activeUsers 1").unwrap();
+
+        let builder = match stage4 {
+            Stage4Rep::Query(query) => Stage5Builder::new(query, &server),
+            _ => panic!("this must be a query"),
+        };
+
+        let result = builder.try_build();
+
+        assert!(matches!(
+            result,
+            Err(QueryBuildError::MissingPrimaryKey(_, TableKind::View, _))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_column() {
+        let server = server(vec![("default", vec![table("users", &["id", "name"])])]);
+
+        assert!(build("users | s: id NAME | w: upper(name) = 'A' | o: id", &server).is_ok());
+        // Tables we don't know about are not checked.
+        assert!(build("guests | s: nmae", &server).is_ok());
+
+        for input in [
+            "users | s: nmae",
+            "users | w: count(nmae) > 1",
+            "users | o: nmae",
+            "users | g: nmae",
+            "users nmae?",
+        ] {
+            match build(input, &server) {
+                Err(QueryBuildError::ColumnNotFound(table, column, suggestions)) => {
+                    assert_eq!(table.it.as_str(), "users");
+                    assert_eq!(column.it.0, "nmae");
+                    assert_eq!(suggestions, ["name"]);
+                }
+                other => panic!("{input} should fail, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unknown_join_column() {
+        let server = server(vec![(
+            "default",
+            vec![
+                table("people", &["id", "name"]),
+                table("preferences", &["id", "personId"]),
+            ],
+        )]);
+
+        assert!(build("people | j: preferences id = personId", &server).is_ok());
+
+        // The left side belongs to the previous table, the right side to the joined one.
+        for (input, expected_table, expected_column) in [
+            ("people | j: preferences idd = personId", "people", "idd"),
+            (
+                "people | j: preferences id = person",
+                "preferences",
+                "person",
+            ),
+        ] {
+            match build(input, &server) {
+                Err(QueryBuildError::ColumnNotFound(table, column, _)) => {
+                    assert_eq!(table.it.as_str(), expected_table);
+                    assert_eq!(column.it.0, expected_column);
+                }
+                other => panic!("{input} should fail, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_case_on_postgres() {
+        let mut server = server(vec![("public", vec![table("users", &["id", "userName"])])]);
+
+        assert!(build("users | s: username", &server).is_ok());
+
+        server.params.db_type = DBType::PostgresSQL;
+        server.params.default_schema = Some("public".into());
+        assert!(build("users | s: userName", &server).is_ok());
+        assert!(matches!(
+            build("users | s: username", &server),
+            Err(QueryBuildError::ColumnNotFound(..))
+        ));
+    }
+
+    #[test]
+    fn test_unindexed_columns() {
+        let users = Table {
+            indexes: vec![Index {
+                name: "PRIMARY".to_string(),
+                columns: vec!["id".into()],
+                unique: true,
+            }],
+            ..table("users", &["id", "name"])
+        };
+        let view = Table {
+            kind: TableKind::View,
+            ..table("activeUsers", &["id", "name"])
+        };
+        let server = server(vec![(
+            "default",
+            vec![users, view, table("guests", &["id", "name"])],
+        )]);
+        let warnings = |input| {
+            build(input, &server)
+                .unwrap()
+                .warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            warnings("users | w: id = 1 name = 'a'"),
+            ["users.name is not indexed, filtering or ordering by it might be slow"]
+        );
+        // MariaDB doesn't care about the case, so neither do the warnings.
+        assert_eq!(warnings("users | w: NAME = 'a'").len(), 1);
+        assert!(warnings("activeUsers | w: name = 'a'").is_empty());
+        // Analyzed before indexes were recorded, or just too small to need them.
+        assert!(warnings("guests | w: name = 'a'").is_empty());
+    }
 }
@@ -0,0 +1,91 @@
+//! Typos are the most common reason a pine fails, so when a name is not found we look for the
+//! names the user most likely meant.
+
+/// At most this many suggestions are shown.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Candidates that differ from `name` by a few edits, or start with it, ignoring case. The
+/// closest ones come first.
+pub fn closest_names<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let name = name.to_lowercase();
+    // Short names would match almost anything with a larger distance.
+    let max_distance = name.chars().count().div_ceil(3);
+
+    let mut matches: Vec<_> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let lowercase = candidate.to_lowercase();
+            let distance = edit_distance(&name, &lowercase);
+
+            if distance <= max_distance || lowercase.starts_with(&name) {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort();
+    matches.dedup();
+
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Levenshtein distance counted in characters, where swapping two neighboring characters is a
+/// single edit. That's the typo we make the most.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i chars of a and the first j of b.
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_names() {
+        let tables = [
+            "users",
+            "userPreferences",
+            "orders",
+            "orderItems",
+            "invoices",
+        ];
+
+        assert_eq!(closest_names("usres", tables), ["users"]);
+        assert_eq!(closest_names("Orders", tables), ["orders"]);
+        assert_eq!(closest_names("order", tables), ["orders", "orderItems"]);
+        assert_eq!(closest_names("userpreference", tables), ["userPreferences"]);
+        assert_eq!(closest_names("user", tables), ["users", "userPreferences"]);
+        assert!(closest_names("payments", tables).is_empty());
+    }
+}
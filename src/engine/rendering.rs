@@ -1,11 +1,15 @@
 pub use columns::render_columns;
-pub use neighbors::render_neighbors;
+pub use indexes::render_indexes;
+pub use neighbors::{render_neighbor_tree, render_neighbors};
 pub use query_rendering::render_query;
 use std::fmt::{Display, Formatter};
+pub use tables::{render_databases, render_tables};
 
 mod columns;
+mod indexes;
 mod neighbors;
 mod query_rendering;
+mod tables;
 
 struct OptionalClause<'a, T> {
     intro: &'a str,
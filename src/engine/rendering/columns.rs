@@ -4,9 +4,32 @@ use crate::engine::syntax::TableInput;
 pub fn render_columns(table: TableInput, columns: &[Column]) -> String {
     let mut buffer = format!("/*\nColumns for `{}`:\n", table.table.it.name);
 
+    // Aligning everything makes this a lot more readable.
+    let name_width = columns.iter().map(|c| c.name.0.len()).max().unwrap_or(0);
+    let type_width = columns.iter().map(|c| c.data_type.len()).max().unwrap_or(0);
+
     for column in columns {
-        buffer.push_str("  ");
-        buffer.push_str(column.name.0.as_str());
+        let mut line = format!("  {:name_width$}", column.name.0);
+
+        // Columns from old analyses don't have any type info.
+        if !column.data_type.is_empty() {
+            line.push_str(&format!("  {:type_width$}", column.data_type));
+            line.push_str(if column.nullable {
+                "  null"
+            } else {
+                "  not null"
+            });
+
+            if let Some(default) = &column.default {
+                line.push_str(&format!(" default {default}"));
+            }
+
+            if column.auto_increment {
+                line.push_str(" auto_increment");
+            }
+        }
+
+        buffer.push_str(line.trim_end());
         buffer.push('\n');
     }
 
@@ -0,0 +1,24 @@
+use crate::analyze::Index;
+use crate::engine::syntax::TableInput;
+
+pub fn render_indexes(table: TableInput, indexes: &[Index]) -> String {
+    let mut buffer = format!("/*\nIndexes for `{}`:\n", table.table.it.name);
+
+    let name_width = indexes.iter().map(|i| i.name.len()).max().unwrap_or(0);
+
+    for index in indexes {
+        let columns: Vec<_> = index.columns.iter().map(|c| c.0.as_str()).collect();
+        let mut line = format!("  {:name_width$}  ({})", index.name, columns.join(", "));
+
+        if index.unique {
+            line.push_str(" unique");
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+    }
+
+    buffer.push_str("*/--");
+
+    buffer
+}
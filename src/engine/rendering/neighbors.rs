@@ -1,5 +1,7 @@
-use crate::analyze::ForeignKey;
+use crate::analyze::{ForeignKey, Key};
+use crate::engine::query_builder::{Neighbor, NeighborDirection};
 use crate::engine::rendering::OptionalClause;
+use crate::engine::syntax::{OptionalInput, TableInput};
 
 pub fn render_neighbors(neighbors: Vec<ForeignKey>) -> String {
     // We wrap the response in a comment. This makes sure we probably won't actually
@@ -7,8 +9,12 @@ pub fn render_neighbors(neighbors: Vec<ForeignKey>) -> String {
     let mut rendering = "/*\nForeign keys to:\n".to_string();
 
     for fk in neighbors {
+        let database = match &fk.to.database {
+            Some(database) => format!("{}.", database.0),
+            None => String::new(),
+        };
         let intro = format!(
-            "{}.{} using",
+            "{database}{}.{} using",
             fk.to.table,
             fk.to
                 .key
@@ -40,3 +46,77 @@ pub fn render_neighbors(neighbors: Vec<ForeignKey>) -> String {
 
     rendering
 }
+
+/// Each line says which way the key goes, the columns it uses, and the pine joining all the
+/// tables from the root down to that line.
+pub fn render_neighbor_tree(table: TableInput, depth: usize, neighbors: &[Neighbor]) -> String {
+    let root = match table.database {
+        OptionalInput::Specified(database) => {
+            format!("{}.{}", database.it.name, table.table.it.name)
+        }
+        OptionalInput::Implicit => table.table.it.name.to_string(),
+    };
+    let mut lines = Vec::new();
+    collect_tree_lines(&root, &root, 1, neighbors, &mut lines);
+
+    // Aligning the pines makes them easier to find and copy.
+    let width = lines
+        .iter()
+        .map(|(entry, _)| entry.len())
+        .max()
+        .unwrap_or(0);
+    let mut rendering = format!("/*\nNeighbors of `{root}`, up to {depth} joins away:\n");
+
+    for (entry, pine) in lines {
+        rendering.push_str(&format!("{entry:width$}  {pine}\n"));
+    }
+
+    rendering.push_str("*/--");
+
+    rendering
+}
+
+fn collect_tree_lines(
+    parent: &str,
+    pine: &str,
+    level: usize,
+    neighbors: &[Neighbor],
+    lines: &mut Vec<(String, String)>,
+) {
+    for neighbor in neighbors {
+        let fk = &neighbor.foreign_key;
+        let name = match &neighbor.database {
+            Some(database) => format!("{}.{}", database.0, fk.to.table),
+            None => fk.to.table.to_string(),
+        };
+        let columns = |table: &str, key: &Key| {
+            let columns: Vec<_> = key.columns.iter().map(|c| c.0.as_str()).collect();
+
+            format!("{table}.{}", columns.join("+"))
+        };
+        // Keys are shown the way they are declared.
+        let key = match neighbor.direction {
+            NeighborDirection::Outgoing => format!(
+                "{} -> {}",
+                columns(parent, &fk.from.key),
+                columns(&name, &fk.to.key)
+            ),
+            NeighborDirection::Incoming => format!(
+                "{} -> {}",
+                columns(&name, &fk.to.key),
+                columns(parent, &fk.from.key)
+            ),
+        };
+        let direction = match neighbor.direction {
+            NeighborDirection::Outgoing => "outgoing",
+            NeighborDirection::Incoming => "incoming",
+        };
+        let neighbor_pine = format!("{pine} | {name}");
+
+        lines.push((
+            format!("{}{direction} {key}", "  ".repeat(level)),
+            neighbor_pine.clone(),
+        ));
+        collect_tree_lines(&name, &neighbor_pine, level + 1, &neighbor.children, lines);
+    }
+}
@@ -1,6 +1,6 @@
-use crate::analyze::{ColumnName, DatabaseName};
+use crate::analyze::{ColumnName, DatabaseName, ForeignKeyKind};
 use crate::engine::query_builder::{
-    Computation, ExplicitJoin, FunctionCall, Query, Selectable, SelectedColumn, Table,
+    Computation, ExplicitJoin, FunctionCall, Query, QueryWarning, Selectable, SelectedColumn, Table,
 };
 use crate::engine::rendering::OptionalClause;
 use crate::engine::{
@@ -16,6 +16,11 @@ pub fn render_query(query: Query) -> String {
 
 impl Display for Query {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Warnings go in SQL comments, so the output can still be executed as is.
+        for warning in &self.warnings {
+            writeln!(f, "-- Warning: {warning}")?;
+        }
+
         writeln!(f, "SELECT {}", RenderableSelect(self.select.as_slice()))?;
         writeln!(f, "FROM {}", self.from)?;
 
@@ -73,6 +78,7 @@ impl Display for ExplicitJoin {
             join_type,
             target_table,
             conditions,
+            key_kind,
         } = self;
 
         write!(f, "{join_type} {target_table} ON ")?;
@@ -87,6 +93,10 @@ impl Display for ExplicitJoin {
             write!(f, " AND {condition}")?;
         }
 
+        if *key_kind == Some(ForeignKeyKind::Inferred) {
+            write!(f, " /* inferred */")?;
+        }
+
         Ok(())
     }
 }
@@ -238,6 +248,17 @@ impl Display for ColumnName {
     }
 }
 
+impl Display for QueryWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryWarning::UnindexedColumn(table, column) => write!(
+                f,
+                "{table}.{column} is not indexed, filtering or ordering by it might be slow"
+            ),
+        }
+    }
+}
+
 impl Display for Table {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if let Some(database) = &self.db {
@@ -0,0 +1,50 @@
+use crate::analyze::{DBType, Database, DatabaseName, ServerParams, Table, TableKind};
+
+pub fn render_tables(database: &Database, filter: Option<&str>, tables: &[&Table]) -> String {
+    let mut buffer = match filter {
+        Some(filter) => format!("/*\nTables in `{}` matching `{filter}`:\n", database.name.0),
+        None => format!("/*\nTables in `{}`:\n", database.name.0),
+    };
+
+    let name_width = tables
+        .iter()
+        .map(|t| t.name.as_str().len())
+        .max()
+        .unwrap_or(0);
+
+    for table in tables {
+        let line = match table.kind {
+            TableKind::Table => format!("  {}", table.name),
+            TableKind::View => format!("  {:name_width$}  view", table.name.as_str()),
+        };
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+    }
+
+    buffer.push_str("*/--");
+
+    buffer
+}
+
+pub fn render_databases(params: &ServerParams, databases: &[&DatabaseName]) -> String {
+    // Postgres has schemas where MariaDB has databases, pines use both the same way.
+    let mut buffer = match params.db_type {
+        DBType::PostgresSQL => "/*\nSchemas:\n".to_string(),
+        DBType::MariaDB => "/*\nDatabases:\n".to_string(),
+    };
+
+    let default = params.default_database();
+
+    for database in databases {
+        if Some(*database) == default {
+            buffer.push_str(&format!("  {} (default)\n", database.0));
+        } else {
+            buffer.push_str(&format!("  {}\n", database.0));
+        }
+    }
+
+    buffer.push_str("*/--");
+
+    buffer
+}
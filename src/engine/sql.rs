@@ -1,6 +1,15 @@
 /// Parses CREATE TABLE queries into Database instances.
 #[cfg(test)]
 mod create_table_parsing;
+/// Lists the differences between two database structures.
+pub mod diff;
+/// Servers and tables for unit tests.
+#[cfg(test)]
+pub mod fixtures;
+/// Which tables the foreign keys connect, in both directions.
+pub mod graph;
+/// Guesses foreign keys for databases that don't declare them.
+pub mod inference;
 pub mod querying;
 /// Structs used to represent database structure.
 pub mod structure;
@@ -23,8 +23,9 @@
 //! dialect.
 use super::{DbStructureParseError, InputWindow};
 use crate::analyze::DbStructureParsingContext;
-use crate::engine::sql::structure::{Column, ForeignKey, Key, KeyReference, Table, TableName};
-use crate::error::InternalError;
+use crate::engine::sql::structure::{
+    Column, ForeignKey, ForeignKeyKind, Index, Key, KeyReference, Table, TableKind, TableName,
+};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use std::iter::{Enumerate, Peekable};
@@ -32,17 +33,43 @@ use std::str::Lines;
 
 impl Column {
     fn from_sql_string(input: &str) -> Result<Self, String> {
-        static COLUMN_NAME_REGEX: Lazy<Regex> =
-            Lazy::new(|| Regex::new("(?i)^`([a-z0-9_]+)` ").unwrap());
-        let matches = COLUMN_NAME_REGEX.captures(input.trim_start());
+        static COLUMN_REGEX: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"(?i)^`(?<name>[a-z0-9_]+)`\s+(?<type>[a-z]+(\s*\([^)]*\))?( unsigned)?)")
+                .unwrap()
+        });
+        static DEFAULT_REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"(?i)\bdefault\s+(?<default>'[^']*'|[^\s,]+)").unwrap());
+        // Defaults and comments can contain anything, so we only look for keywords outside them.
+        static STRING_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"'[^']*'").unwrap());
+        static NOT_NULL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bnot\s+null\b").unwrap());
+        static AUTO_INCREMENT_REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"\bauto_increment\b").unwrap());
+
+        let input = input.trim();
+        let matches = COLUMN_REGEX.captures(input);
 
         if let Some(captures) = matches {
+            let name = captures
+                .name("name")
+                .expect("The regex makes this always be here");
+            let data_type = captures
+                .name("type")
+                .expect("The regex makes this always be here");
+            let rest = input[data_type.end()..].to_lowercase();
+            let rest = STRING_REGEX.replace_all(&rest, "''");
+
+            let default = DEFAULT_REGEX
+                .captures(&input[data_type.end()..])
+                .and_then(|captures| captures.name("default"))
+                .map(|default| default.as_str().to_string())
+                .filter(|default| default.to_lowercase() != "null");
+
             Ok(Column {
-                name: captures
-                    .get(1)
-                    .expect("The regex makes this always be here")
-                    .as_str()
-                    .into(),
+                name: name.as_str().into(),
+                data_type: data_type.as_str().to_string(),
+                nullable: !NOT_NULL_REGEX.is_match(&rest),
+                default,
+                auto_increment: AUTO_INCREMENT_REGEX.is_match(&rest),
             })
         } else {
             Err(format!("Invalid column spec: \"{}\"", input))
@@ -81,6 +108,7 @@ impl ForeignKey {
             Ok(ForeignKey {
                 from: KeyReference::from_sql_str(from_table, from_keys_source.as_str()),
                 to: KeyReference::from_sql_str(to_table_source.as_str(), to_keys_source.as_str()),
+                kind: ForeignKeyKind::Declared,
             })
         } else {
             Err(format!("Invalid foreign key spec: \"{}\"", input))
@@ -88,6 +116,37 @@ impl ForeignKey {
     }
 }
 
+#[cfg(test)]
+impl Index {
+    fn from_sql_string(input: &str) -> Result<Self, String> {
+        static INDEX_LINE_REGEX: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(
+                r"(?i)^(?<unique>UNIQUE\s+)?(KEY|INDEX)\s+`(?<name>[^`]+)`\s*\((?<columns>[^)]+)\)",
+            )
+            .unwrap()
+        });
+
+        let matches = INDEX_LINE_REGEX.captures(input.trim_start());
+
+        if let Some(captures) = matches {
+            let name = captures
+                .name("name")
+                .expect("name capture group not optional");
+            let columns = captures
+                .name("columns")
+                .expect("columns capture group not optional");
+
+            Ok(Index {
+                name: name.as_str().to_string(),
+                columns: KeyReference::from_sql_str("", columns.as_str()).key.columns,
+                unique: captures.name("unique").is_some(),
+            })
+        } else {
+            Err(format!("Invalid index spec: \"{}\"", input))
+        }
+    }
+}
+
 #[cfg(test)]
 impl KeyReference {
     fn from_sql_str(table: &str, input: &str) -> Self {
@@ -104,7 +163,11 @@ impl KeyReference {
         let table = table.into();
         let key = matches.as_slice().into();
 
-        Self { table, key }
+        Self {
+            database: None,
+            table,
+            key,
+        }
     }
 }
 
@@ -158,15 +221,55 @@ impl Table {
         };
 
         let name: TableName = Self::parse_table_name_line(&window, &mut lines)?.into();
-        let columns = Self::parse_columns(&mut lines);
+        let mut columns = Self::parse_columns(&mut lines);
         let primary_key = Self::parse_primary_key(&window, &mut lines)?;
-        let foreign_keys = Self::parse_foreign_keys(name.as_str(), &mut lines);
+
+        // Primary key columns are implicitly "not null", even if the column spec does not say so.
+        for column in &mut columns {
+            if let Some(primary_key) = &primary_key {
+                column.nullable &= !primary_key.columns.contains(&column.name);
+            }
+        }
+        let (mut indexes, foreign_keys) = Self::parse_keys(name.as_str(), &mut lines);
+
+        if let Some(primary_key) = &primary_key {
+            indexes.insert(
+                0,
+                Index {
+                    name: "PRIMARY".to_string(),
+                    columns: primary_key.columns.clone(),
+                    unique: true,
+                },
+            );
+        }
+
+        // Like InnoDB, we assume each foreign key gets an index, unless it's already covered.
+        for foreign_key in &foreign_keys {
+            let columns = &foreign_key.from.key.columns;
+            let is_covered = indexes
+                .iter()
+                .any(|index| index.columns.starts_with(columns));
+
+            if !is_covered {
+                indexes.push(Index {
+                    name: columns
+                        .iter()
+                        .map(|column| column.0.as_str())
+                        .collect::<Vec<_>>()
+                        .join("_"),
+                    columns: columns.clone(),
+                    unique: false,
+                });
+            }
+        }
 
         Ok(Table {
             name,
+            kind: TableKind::Table,
             primary_key,
             columns,
             foreign_keys,
+            indexes,
         })
     }
 
@@ -220,46 +323,59 @@ impl Table {
         columns
     }
 
+    /// Tables without a primary key go straight from the columns to the other keys.
     fn parse_primary_key(
         window: &InputWindow,
-        lines: &mut dyn Iterator<Item = (usize, &str)>,
-    ) -> Result<Key, crate::Error> {
-        if let Some(line_item) = lines.next() {
-            let (line_number, table_name_line) = line_item;
+        lines: &mut Peekable<Enumerate<Lines>>,
+    ) -> Result<Option<Key>, crate::Error> {
+        let is_primary_key_line =
+            |(_, line): &(usize, &str)| line.trim_start().to_lowercase().starts_with("primary key");
+
+        let (line_number, primary_key_line) = match lines.next_if(is_primary_key_line) {
+            Some(line_item) => line_item,
+            None => return Ok(None),
+        };
 
-            static PRIMARY_KEY_SQL_LINE_REGEX: Lazy<Regex> = Lazy::new(|| {
-                Regex::new(r"(?i)^\s*PRIMARY KEY \((?<key>((`[a-z0-9_]+`),?\s?)+)\)").unwrap()
-            });
-            let matches = PRIMARY_KEY_SQL_LINE_REGEX.captures(table_name_line);
+        static PRIMARY_KEY_SQL_LINE_REGEX: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"(?i)^\s*PRIMARY KEY \((?<key>((`[a-z0-9_]+`),?\s?)+)\)").unwrap()
+        });
+        let matches = PRIMARY_KEY_SQL_LINE_REGEX.captures(primary_key_line);
 
-            if let Some(captures) = matches {
-                let table_names = captures.get(1).expect("Key group is not optional");
+        if let Some(captures) = matches {
+            let table_names = captures.get(1).expect("Key group is not optional");
 
-                Ok(Key::try_from_sql_string(window, table_names.as_str())?)
-            } else {
-                Err(DbStructureParseError {
-                    line_number,
-                    message: format!("Unsupported primary key spec:\n{}", table_name_line),
-                    input: window.clone(),
-                })?
-            }
+            Ok(Some(Key::try_from_sql_string(
+                window,
+                table_names.as_str(),
+            )?))
         } else {
-            Err(InternalError("Primary Key line not found".to_string()))?
+            Err(DbStructureParseError {
+                line_number,
+                message: format!("Unsupported primary key spec:\n{}", primary_key_line),
+                input: window.clone(),
+            })?
         }
     }
 
     /// Consumes the rest of the iterator
-    fn parse_foreign_keys(
+    fn parse_keys(
         table: &str,
         lines: &mut dyn Iterator<Item = (usize, &str)>,
-    ) -> Vec<ForeignKey> {
-        lines
-            .filter_map(|fk| match ForeignKey::from_sql_string(table, fk.1) {
-                Ok(fk) => Some(fk),
-                // This means we've gone past the FK section in the create table statement.
-                Err(_) => None,
-            })
-            .collect()
+    ) -> (Vec<Index>, Vec<ForeignKey>) {
+        let mut indexes = Vec::new();
+        let mut foreign_keys = Vec::new();
+
+        // Lines that are neither are either constraint names on their own line or the end of the
+        // create table statement.
+        for (_, line) in lines {
+            if let Ok(index) = Index::from_sql_string(line) {
+                indexes.push(index);
+            } else if let Ok(foreign_key) = ForeignKey::from_sql_string(table, line) {
+                foreign_keys.push(foreign_key);
+            }
+        }
+
+        (indexes, foreign_keys)
     }
 }
 
@@ -273,6 +389,32 @@ mod tests {
         let column = Column::from_sql_string(input).unwrap();
 
         assert_eq!(column.name, "id");
+        assert_eq!(column.data_type, "int(11)");
+        assert!(!column.nullable);
+        assert!(column.auto_increment);
+        assert_eq!(column.default, None);
+    }
+
+    #[test]
+    fn test_parse_column_details() {
+        let input = "`status` enum ('new', 'old') COLLATE utf8_unicode_ci DEFAULT 'new',";
+        let column = Column::from_sql_string(input).unwrap();
+
+        assert_eq!(column.name, "status");
+        assert_eq!(column.data_type, "enum ('new', 'old')");
+        assert!(column.nullable);
+        assert!(!column.auto_increment);
+        assert_eq!(column.default.as_deref(), Some("'new'"));
+    }
+
+    #[test]
+    fn test_parse_column_keywords_in_strings() {
+        let input = "`note` varchar(20) DEFAULT 'not null' COMMENT 'never auto_increment',";
+        let column = Column::from_sql_string(input).unwrap();
+
+        assert!(column.nullable);
+        assert!(!column.auto_increment);
+        assert_eq!(column.default.as_deref(), Some("'not null'"));
     }
 
     #[test]
@@ -305,8 +447,42 @@ CREATE TABLE `teams` (
         let table = Table::from_sql_string(&Default::default(), input).unwrap();
 
         assert_eq!(table.name, "teams");
-        assert_eq!(table.primary_key.columns.len(), 2);
         assert_eq!(table.columns.len(), 4);
         assert_eq!(table.foreign_keys.len(), 2);
+        assert_eq!(table.indexes.len(), 3);
+        assert!(table.is_indexed("parentId"));
+        assert!(!table.is_indexed("id2"));
+        assert_eq!(table.primary_key.unwrap().columns.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_table_without_primary_key() {
+        let input = "
+CREATE TABLE `teamLogs` (
+  `teamId` int(11) NOT NULL,
+  `message` text COLLATE utf8_unicode_ci NOT NULL,
+  KEY `IDX_96C22258F17FD7A6` (`teamId`),
+  CONSTRAINT `FK_96C22258F17FD7A6` FOREIGN KEY (`teamId`) REFERENCES `teams` (`id`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8 COLLATE=utf8_unicode_ci
+";
+        let table = Table::from_sql_string(&Default::default(), input).unwrap();
+
+        assert_eq!(table.name, "teamLogs");
+        assert!(table.primary_key.is_none());
+        assert_eq!(table.columns.len(), 2);
+        assert_eq!(table.foreign_keys.len(), 1);
+        assert_eq!(table.indexes.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let input = "UNIQUE KEY `UNIQ_email` (`email`, `deletedAt`),";
+        let index = Index::from_sql_string(input).unwrap();
+
+        assert_eq!(index.name, "UNIQ_email");
+        assert_eq!(index.columns.len(), 2);
+        assert_eq!(index.columns[0], "email");
+        assert_eq!(index.columns[1], "deletedAt");
+        assert!(index.unique);
     }
 }
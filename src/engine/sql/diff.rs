@@ -0,0 +1,318 @@
+//! Compares two database structures, like two analyses of the same server or the servers of two
+//! contexts, and lists what changed between them.
+use crate::analyze::{ColumnName, Database, DatabaseName, ForeignKey, Key, Table, TableName};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    AddedDatabase(DatabaseName),
+    DroppedDatabase(DatabaseName),
+    AddedTable(DatabaseName, TableName),
+    DroppedTable(DatabaseName, TableName),
+    AddedColumn(DatabaseName, TableName, ColumnName),
+    DroppedColumn(DatabaseName, TableName, ColumnName),
+    ChangedPrimaryKey {
+        database: DatabaseName,
+        table: TableName,
+        before: Option<Key>,
+        after: Option<Key>,
+    },
+    AddedForeignKey(DatabaseName, ForeignKey),
+    DroppedForeignKey(DatabaseName, ForeignKey),
+}
+
+/// Changes are sorted by database and table, so the output is stable.
+pub fn diff_databases(
+    before: &HashMap<DatabaseName, Database>,
+    after: &HashMap<DatabaseName, Database>,
+) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+    let names: BTreeSet<_> = before.keys().chain(after.keys()).map(|db| &db.0).collect();
+
+    for name in names {
+        let name = DatabaseName(name.clone());
+
+        match (before.get(&name), after.get(&name)) {
+            (Some(_), None) => changes.push(SchemaChange::DroppedDatabase(name)),
+            (None, Some(_)) => changes.push(SchemaChange::AddedDatabase(name)),
+            (Some(before), Some(after)) => diff_tables(&name, before, after, &mut changes),
+            (None, None) => unreachable!("The name comes from one of the maps"),
+        }
+    }
+
+    changes
+}
+
+fn diff_tables(
+    database: &DatabaseName,
+    before: &Database,
+    after: &Database,
+    changes: &mut Vec<SchemaChange>,
+) {
+    let names: BTreeSet<_> = before
+        .tables
+        .iter()
+        .chain(after.tables.iter())
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    for name in names {
+        let name = TableName::from(name);
+
+        match (before.tables.get(&name), after.tables.get(&name)) {
+            (Some(_), None) => changes.push(SchemaChange::DroppedTable(database.clone(), name)),
+            (None, Some(_)) => changes.push(SchemaChange::AddedTable(database.clone(), name)),
+            (Some(before), Some(after)) => diff_table(database, before, after, changes),
+            (None, None) => unreachable!("The name comes from one of the maps"),
+        }
+    }
+}
+
+fn diff_table(
+    database: &DatabaseName,
+    before: &Table,
+    after: &Table,
+    changes: &mut Vec<SchemaChange>,
+) {
+    let has_column =
+        |table: &Table, name: &ColumnName| table.columns.iter().any(|column| &column.name == name);
+
+    for column in &before.columns {
+        if !has_column(after, &column.name) {
+            changes.push(SchemaChange::DroppedColumn(
+                database.clone(),
+                before.name.clone(),
+                column.name.clone(),
+            ));
+        }
+    }
+    for column in &after.columns {
+        if !has_column(before, &column.name) {
+            changes.push(SchemaChange::AddedColumn(
+                database.clone(),
+                after.name.clone(),
+                column.name.clone(),
+            ));
+        }
+    }
+
+    if before.primary_key != after.primary_key {
+        changes.push(SchemaChange::ChangedPrimaryKey {
+            database: database.clone(),
+            table: after.name.clone(),
+            before: before.primary_key.clone(),
+            after: after.primary_key.clone(),
+        });
+    }
+
+    for foreign_key in &before.foreign_keys {
+        if !after.foreign_keys.contains(foreign_key) {
+            changes.push(SchemaChange::DroppedForeignKey(
+                database.clone(),
+                foreign_key.clone(),
+            ));
+        }
+    }
+    for foreign_key in &after.foreign_keys {
+        if !before.foreign_keys.contains(foreign_key) {
+            changes.push(SchemaChange::AddedForeignKey(
+                database.clone(),
+                foreign_key.clone(),
+            ));
+        }
+    }
+}
+
+impl SchemaChange {
+    pub fn database(&self) -> &DatabaseName {
+        match self {
+            SchemaChange::AddedDatabase(database)
+            | SchemaChange::DroppedDatabase(database)
+            | SchemaChange::AddedTable(database, _)
+            | SchemaChange::DroppedTable(database, _)
+            | SchemaChange::AddedColumn(database, _, _)
+            | SchemaChange::DroppedColumn(database, _, _)
+            | SchemaChange::ChangedPrimaryKey { database, .. }
+            | SchemaChange::AddedForeignKey(database, _)
+            | SchemaChange::DroppedForeignKey(database, _) => database,
+        }
+    }
+
+    /// The table that changed, for changes inside a table. Added and dropped tables don't count,
+    /// they are changes of their database.
+    pub fn changed_table(&self) -> Option<&TableName> {
+        match self {
+            SchemaChange::AddedColumn(_, table, _)
+            | SchemaChange::DroppedColumn(_, table, _)
+            | SchemaChange::ChangedPrimaryKey { table, .. } => Some(table),
+            SchemaChange::AddedForeignKey(_, foreign_key)
+            | SchemaChange::DroppedForeignKey(_, foreign_key) => Some(&foreign_key.from.table),
+            _ => None,
+        }
+    }
+
+    /// Describes the change without repeating where it happened, see changed_table().
+    pub fn describe(&self) -> String {
+        let mut description = String::new();
+        self.write(&mut description, false)
+            .expect("Writing to a String can't fail");
+
+        description
+    }
+
+    /// Display lists the database and table of every change, describe() leaves them out.
+    fn write(&self, f: &mut impl std::fmt::Write, qualified: bool) -> std::fmt::Result {
+        let table_path = |database: &DatabaseName| match qualified {
+            true => format!("{}.", database.0),
+            false => String::new(),
+        };
+        let column_path = |database: &DatabaseName, table: &TableName| match qualified {
+            true => format!("{}.{table}.", database.0),
+            false => String::new(),
+        };
+
+        match self {
+            SchemaChange::AddedDatabase(database) => write!(f, "+ database {}", database.0),
+            SchemaChange::DroppedDatabase(database) => write!(f, "- database {}", database.0),
+            SchemaChange::AddedTable(database, table) => {
+                write!(f, "+ table {}{table}", table_path(database))
+            }
+            SchemaChange::DroppedTable(database, table) => {
+                write!(f, "- table {}{table}", table_path(database))
+            }
+            SchemaChange::AddedColumn(database, table, column) => {
+                write!(f, "+ column {}{column}", column_path(database, table))
+            }
+            SchemaChange::DroppedColumn(database, table, column) => {
+                write!(f, "- column {}{column}", column_path(database, table))
+            }
+            SchemaChange::ChangedPrimaryKey {
+                database,
+                table,
+                before,
+                after,
+            } => {
+                let table = match qualified {
+                    true => format!("{}.{table} ", database.0),
+                    false => String::new(),
+                };
+
+                write!(
+                    f,
+                    "~ primary key {table}{} -> {}",
+                    RenderablePrimaryKey(before),
+                    RenderablePrimaryKey(after)
+                )
+            }
+            SchemaChange::AddedForeignKey(database, foreign_key) => {
+                write!(f, "+ foreign key {}", RenderableKey(database, foreign_key))
+            }
+            SchemaChange::DroppedForeignKey(database, foreign_key) => {
+                write!(f, "- foreign key {}", RenderableKey(database, foreign_key))
+            }
+        }
+    }
+}
+
+impl Display for SchemaChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write(f, true)
+    }
+}
+
+/// Same format as `pine relation add`: `db.orders.userId -> db.users.id`.
+struct RenderableKey<'a>(&'a DatabaseName, &'a ForeignKey);
+
+impl Display for RenderableKey<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let RenderableKey(database, foreign_key) = self;
+        let columns = |columns: &[ColumnName]| {
+            columns
+                .iter()
+                .map(|column| column.0.as_str())
+                .collect::<Vec<_>>()
+                .join("+")
+        };
+
+        write!(
+            f,
+            "{}.{}.{} -> {}.{}.{}",
+            database.0,
+            foreign_key.from.table,
+            columns(&foreign_key.from.key.columns),
+            foreign_key.to.database_or(database).0,
+            foreign_key.to.table,
+            columns(&foreign_key.to.key.columns),
+        )
+    }
+}
+
+struct RenderablePrimaryKey<'a>(&'a Option<Key>);
+
+impl Display for RenderablePrimaryKey<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Some(key) => {
+                let columns: Vec<_> = key.columns.iter().map(|c| c.0.as_str()).collect();
+
+                write!(f, "({})", columns.join(", "))
+            }
+            None => write!(f, "none"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::Key;
+    use crate::engine::sql::fixtures::{self, foreign_key, table};
+
+    fn database(tables: Vec<Table>) -> HashMap<DatabaseName, Database> {
+        HashMap::from([fixtures::database("shop", tables)])
+    }
+
+    #[test]
+    fn test_diff_databases() {
+        let before = database(vec![
+            table("users", &["id", "legacyName"]),
+            table("carts", &["id"]),
+            table("orders", &["id", "userId"]),
+        ]);
+        let mut orders = table("orders", &["id", "userId"]);
+        orders
+            .foreign_keys
+            .push(foreign_key("orders.userId", "users.id"));
+        let mut users = table("users", &["id", "email"]);
+        users.primary_key = Some(Key::from(&["id"][..]));
+        let after = database(vec![users, orders]);
+
+        let changes = diff_databases(&before, &after);
+
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            [
+                "- table shop.carts",
+                "+ foreign key shop.orders.userId -> shop.users.id",
+                "- column shop.users.legacyName",
+                "+ column shop.users.email",
+                "~ primary key shop.users none -> (id)",
+            ]
+        );
+        // Grouped under their database and table, the changes don't repeat them.
+        assert_eq!(
+            changes
+                .iter()
+                .map(SchemaChange::describe)
+                .collect::<Vec<_>>(),
+            [
+                "- table carts",
+                "+ foreign key shop.orders.userId -> shop.users.id",
+                "- column legacyName",
+                "+ column email",
+                "~ primary key none -> (id)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Servers and tables for unit tests. Tests that only need a query rendered from a single
+//! database belong in the src/tests/*.sql files instead.
+use crate::analyze::{
+    DBType, Database, DatabaseName, ForeignKey, ForeignKeyKind, Key, KeyReference, Server,
+    ServerParams, Table,
+};
+use std::collections::HashMap;
+
+/// A table without keys or indexes.
+pub fn table(name: &str, columns: &[&str]) -> Table {
+    Table {
+        name: name.into(),
+        kind: Default::default(),
+        primary_key: None,
+        columns: columns.iter().map(|&column| column.into()).collect(),
+        foreign_keys: vec![],
+        indexes: vec![],
+    }
+}
+
+/// A declared key between two columns, written as `table.column` or `database.table.column`.
+pub fn foreign_key(from: &str, to: &str) -> ForeignKey {
+    ForeignKey {
+        from: key_reference(from),
+        to: key_reference(to),
+        kind: ForeignKeyKind::Declared,
+    }
+}
+
+fn key_reference(input: &str) -> KeyReference {
+    let parts: Vec<&str> = input.split('.').collect();
+    let (database, table, column) = match parts.as_slice() {
+        [table, column] => (None, table, column),
+        [database, table, column] => (Some((*database).into()), table, column),
+        _ => panic!("{input} is not a column"),
+    };
+
+    KeyReference {
+        database,
+        table: (*table).into(),
+        key: Key {
+            columns: vec![(*column).into()],
+        },
+    }
+}
+
+pub fn database(name: &str, tables: Vec<Table>) -> (DatabaseName, Database) {
+    (
+        name.into(),
+        Database {
+            name: name.into(),
+            tables: tables
+                .into_iter()
+                .map(|table| (table.name.clone(), table))
+                .collect::<HashMap<_, _>>()
+                .into(),
+        },
+    )
+}
+
+/// A MariaDB server. The first database is the default one.
+pub fn server(databases: Vec<(&str, Vec<Table>)>) -> Server {
+    let default = databases.first().map_or("", |(name, _)| *name);
+
+    // the params don't matter
+    let params = ServerParams {
+        db_type: DBType::MariaDB,
+        hostname: "".to_string(),
+        port: 0,
+        user: "".to_string(),
+        database: default.into(),
+        default_schema: None,
+    };
+
+    Server::new(
+        params,
+        databases
+            .into_iter()
+            .map(|(name, tables)| database(name, tables))
+            .collect(),
+    )
+}
@@ -0,0 +1,166 @@
+//! Finding which tables point to a table means looking at every foreign key of every table. We do
+//! that once per server, and keep the keys indexed by the table on both of their ends.
+use crate::analyze::{Database, DatabaseName, ForeignKey, TableName};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default)]
+pub struct ForeignKeyGraph {
+    outgoing: HashMap<(DatabaseName, TableName), Vec<ForeignKey>>,
+    /// Keys are stored as their table declares them, next to the database of that table.
+    incoming: HashMap<(DatabaseName, TableName), Vec<(DatabaseName, ForeignKey)>>,
+}
+
+impl ForeignKeyGraph {
+    pub fn new(databases: &HashMap<DatabaseName, Database>) -> ForeignKeyGraph {
+        let mut graph = ForeignKeyGraph::default();
+
+        for (database_name, database) in databases {
+            for (table_name, table) in database.tables.iter() {
+                for foreign_key in &table.foreign_keys {
+                    let target = (
+                        foreign_key.to.database_or(database_name).clone(),
+                        foreign_key.to.table.clone(),
+                    );
+
+                    graph
+                        .incoming
+                        .entry(target)
+                        .or_default()
+                        .push((database_name.clone(), foreign_key.clone()));
+                }
+
+                graph.outgoing.insert(
+                    (database_name.clone(), table_name.clone()),
+                    table.foreign_keys.clone(),
+                );
+            }
+        }
+
+        // Tables come out of a hash map, sorting keeps listings from changing between runs.
+        for keys in graph.incoming.values_mut() {
+            keys.sort_by(|(a_database, a), (b_database, b)| {
+                (&a_database.0, a.from.table.as_str(), &a.from.key.columns).cmp(&(
+                    &b_database.0,
+                    b.from.table.as_str(),
+                    &b.from.key.columns,
+                ))
+            });
+        }
+
+        graph
+    }
+
+    /// Keys declared by the table.
+    pub fn outgoing(&self, database: &DatabaseName, table: &TableName) -> &[ForeignKey] {
+        self.outgoing
+            .get(&(database.clone(), table.clone()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Keys pointing to the table, with the database of the table declaring them.
+    pub fn incoming(
+        &self,
+        database: &DatabaseName,
+        table: &TableName,
+    ) -> &[(DatabaseName, ForeignKey)] {
+        self.incoming
+            .get(&(database.clone(), table.clone()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Tables at most `depth` foreign keys away from `start`, following keys in both directions.
+    /// Includes `start`. Stops early once there's nothing new to find, so any depth is fine.
+    pub fn neighborhood(
+        &self,
+        start: (DatabaseName, TableName),
+        depth: usize,
+    ) -> HashSet<(DatabaseName, TableName)> {
+        let mut found = HashSet::from([start.clone()]);
+        let mut frontier = vec![start];
+
+        for _ in 0..depth {
+            if frontier.is_empty() {
+                break;
+            }
+
+            let mut next = Vec::new();
+
+            for (database, table) in &frontier {
+                let targets = self
+                    .outgoing(database, table)
+                    .iter()
+                    .map(|fk| (fk.to.database_or(database).clone(), fk.to.table.clone()));
+                let sources = self
+                    .incoming(database, table)
+                    .iter()
+                    .map(|(source_database, fk)| (source_database.clone(), fk.from.table.clone()));
+
+                for neighbor in targets.chain(sources) {
+                    if found.insert(neighbor.clone()) {
+                        next.push(neighbor);
+                    }
+                }
+            }
+
+            frontier = next;
+        }
+
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyze::Table;
+    use crate::engine::sql::fixtures::{foreign_key, server, table};
+
+    fn node(database: &str, table: &str) -> (DatabaseName, TableName) {
+        (database.into(), table.into())
+    }
+
+    #[test]
+    fn test_neighborhood() {
+        // users <- orders <- orderItems, and orders -> billing.invoices
+        let orders = Table {
+            foreign_keys: vec![
+                foreign_key("orders.userId", "users.id"),
+                foreign_key("orders.invoiceId", "billing.invoices.id"),
+            ],
+            ..table("orders", &["id", "userId", "invoiceId"])
+        };
+        let order_items = Table {
+            foreign_keys: vec![foreign_key("orderItems.orderId", "orders.id")],
+            ..table("orderItems", &["id", "orderId"])
+        };
+        let server = server(vec![
+            ("shop", vec![table("users", &["id"]), orders, order_items]),
+            ("billing", vec![table("invoices", &["id"])]),
+        ]);
+        let graph = server.foreign_key_graph();
+
+        assert_eq!(
+            graph.neighborhood(node("shop", "users"), 0),
+            HashSet::from([node("shop", "users")])
+        );
+        assert_eq!(
+            graph.neighborhood(node("shop", "users"), 1),
+            HashSet::from([node("shop", "users"), node("shop", "orders")])
+        );
+        let everything = HashSet::from([
+            node("shop", "users"),
+            node("shop", "orders"),
+            node("shop", "orderItems"),
+            node("billing", "invoices"),
+        ]);
+        assert_eq!(
+            graph.neighborhood(node("billing", "invoices"), 3),
+            everything
+        );
+        // Has to stop once everything was found.
+        assert_eq!(
+            graph.neighborhood(node("shop", "users"), usize::MAX),
+            everything
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! Legacy schemas (MyISAM-era, mostly) often don't declare any foreign keys, which means we can't
+//! join anything automatically. Luckily, most of them follow naming conventions we can use to
+//! guess the missing keys: `userId`, `user_id` and `users_id` all point to `users.id`.
+use crate::analyze::{ColumnName, ForeignKey, ForeignKeyKind, Key, KeyReference, Table, TableName};
+use std::collections::HashMap;
+
+/// Adds inferred foreign keys to the tables and returns how many were added.
+///
+/// A column is considered a foreign key if its name is the name of another table (singular or
+/// plural) followed by the single-column primary key of that table. We skip columns that are
+/// already part of a declared foreign key, and names that match more than one table.
+pub fn infer_foreign_keys(tables: &mut HashMap<TableName, Table>) -> usize {
+    let targets: Vec<(TableName, ColumnName)> = tables
+        .values()
+        .filter_map(
+            |table| match table.primary_key.as_ref()?.columns.as_slice() {
+                [column] => Some((table.name.clone(), column.clone())),
+                _ => None,
+            },
+        )
+        .collect();
+
+    let mut inferred = 0;
+    for table in tables.values_mut() {
+        let mut foreign_keys = Vec::new();
+
+        for column in &table.columns {
+            let is_declared = table
+                .foreign_keys
+                .iter()
+                .any(|fk| fk.from.key.columns.contains(&column.name));
+            if is_declared {
+                continue;
+            }
+
+            let mut matching_targets = targets.iter().filter(|(target_table, target_column)| {
+                target_table != &table.name
+                    && names_reference(&column.name, target_table, target_column)
+            });
+
+            if let (Some((target_table, target_column)), None) =
+                (matching_targets.next(), matching_targets.next())
+            {
+                foreign_keys.push(ForeignKey {
+                    from: KeyReference {
+                        database: None,
+                        table: table.name.clone(),
+                        key: Key {
+                            columns: vec![column.name.clone()],
+                        },
+                    },
+                    to: KeyReference {
+                        database: None,
+                        table: target_table.clone(),
+                        key: Key {
+                            columns: vec![target_column.clone()],
+                        },
+                    },
+                    kind: ForeignKeyKind::Inferred,
+                });
+            }
+        }
+
+        inferred += foreign_keys.len();
+        table.foreign_keys.extend(foreign_keys);
+    }
+
+    inferred
+}
+
+fn names_reference(column: &ColumnName, table: &TableName, primary_key: &ColumnName) -> bool {
+    let column = normalize(column.0.as_str());
+    let table = normalize(table.as_str());
+    let primary_key = normalize(primary_key.0.as_str());
+
+    column
+        .strip_suffix(primary_key.as_str())
+        .is_some_and(|prefix| prefix == table || prefix == singular(&table))
+}
+
+/// Takes care of both camelCase and snake_case.
+fn normalize(name: &str) -> String {
+    name.replace('_', "").to_lowercase()
+}
+
+/// Good enough for table names, we're not trying to cover all of English here.
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if name.ends_with("ss") {
+        name.to_string()
+    } else {
+        name.strip_suffix('s').unwrap_or(name).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::sql::fixtures::{self, foreign_key, server};
+
+    /// The first column is the primary key.
+    fn table(name: &str, columns: &[&str]) -> Table {
+        Table {
+            primary_key: Some(Key {
+                columns: vec![columns[0].into()],
+            }),
+            ..fixtures::table(name, columns)
+        }
+    }
+
+    fn tables(tables: Vec<Table>) -> HashMap<TableName, Table> {
+        tables
+            .into_iter()
+            .map(|table| (table.name.clone(), table))
+            .collect()
+    }
+
+    #[test]
+    fn test_infers_common_naming_conventions() {
+        let mut tables = tables(vec![
+            table("users", &["id", "name"]),
+            table("categories", &["id"]),
+            table("orders", &["id", "userId", "category_id", "users_id"]),
+        ]);
+
+        let inferred = infer_foreign_keys(&mut tables);
+
+        assert_eq!(inferred, 3);
+        let orders = &tables[&TableName::from("orders")];
+        let targets: Vec<_> = orders
+            .foreign_keys
+            .iter()
+            .map(|fk| (fk.from.key.columns[0].0.as_str(), fk.to.table.as_str()))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                ("userId", "users"),
+                ("category_id", "categories"),
+                ("users_id", "users")
+            ]
+        );
+        assert!(orders
+            .foreign_keys
+            .iter()
+            .all(|fk| fk.kind == ForeignKeyKind::Inferred));
+    }
+
+    #[test]
+    fn test_inferred_joins_are_annotated() {
+        let mut tables = tables(vec![
+            table("users", &["id"]),
+            table("orders", &["id", "userId"]),
+        ]);
+        infer_foreign_keys(&mut tables);
+        let server = server(vec![("default", tables.into_values().collect())]);
+
+        let query = crate::render("users | orders", &server).unwrap();
+
+        assert!(query.contains("LEFT JOIN users ON users.id = orders.userId /* inferred */"));
+    }
+
+    #[test]
+    fn test_skips_declared_and_ambiguous_columns() {
+        let mut orders = table("orders", &["id", "userId", "accountId"]);
+        orders
+            .foreign_keys
+            .push(foreign_key("orders.userId", "users.id"));
+        let mut tables = tables(vec![
+            table("users", &["id"]),
+            table("account", &["id"]),
+            table("accounts", &["id"]),
+            orders,
+        ]);
+
+        let inferred = infer_foreign_keys(&mut tables);
+
+        assert_eq!(inferred, 0);
+    }
+}
@@ -3,7 +3,7 @@ mod postgres;
 
 use std::fmt::{Display, Formatter};
 
-use crate::analyze::{Column, ForeignKey, Key, ServerParams, TableName};
+use crate::analyze::{Column, ForeignKey, Index, Key, ServerParams, TableKind, TableName};
 use crate::Error;
 use async_trait::async_trait;
 use sqlx::postgres::PgPoolOptions;
@@ -31,7 +31,10 @@ pub fn to_id(db_identifier: String) -> SchemaObjectName {
 pub trait Analyzer {
     async fn list_databases(&self) -> Result<Vec<SchemaObjectName>, Error>;
 
-    async fn list_tables(&self, database: &SchemaObjectName) -> Result<Vec<TableName>, Error>;
+    async fn list_tables(
+        &self,
+        database: &SchemaObjectName,
+    ) -> Result<Vec<(TableName, TableKind)>, Error>;
 
     async fn table_columns(
         &self,
@@ -47,6 +50,11 @@ pub trait Analyzer {
         &self,
         database: &SchemaObjectName,
     ) -> Result<HashMap<TableName, Key>, Error>;
+
+    async fn table_indexes(
+        &self,
+        database: &SchemaObjectName,
+    ) -> Result<HashMap<TableName, Vec<Index>>, Error>;
 }
 
 pub struct Connection<T> {
@@ -99,6 +107,12 @@ impl Display for SchemaObjectName {
     }
 }
 
+impl AsRef<str> for SchemaObjectName {
+    fn as_ref(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
 impl SchemaObjectName {
     pub fn as_str(&self) -> &str {
         self.0.as_str()
@@ -1,6 +1,7 @@
 use super::Connection;
 use crate::analyze::{
-    Column, ColumnName, ForeignKey, Key, KeyReference, SchemaObjectName, TableName,
+    Column, ColumnName, DatabaseName, ForeignKey, ForeignKeyKind, Index, Key, KeyReference,
+    SchemaObjectName, TableKind, TableName,
 };
 use crate::engine::sql::querying::to_id;
 use crate::engine::sql::querying::Analyzer;
@@ -24,9 +25,12 @@ impl Analyzer for Connection<Pool<MariaDB>> {
         Ok(rows)
     }
 
-    async fn list_tables(&self, database: &SchemaObjectName) -> Result<Vec<TableName>, Error> {
-        let rows: Vec<(String,)> = sqlx::query_as(
-            "SELECT TABLE_NAME\n\
+    async fn list_tables(
+        &self,
+        database: &SchemaObjectName,
+    ) -> Result<Vec<(TableName, TableKind)>, Error> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT TABLE_NAME, TABLE_TYPE\n\
              FROM information_schema.TABLES\n\
              WHERE TABLE_SCHEMA = ?",
         )
@@ -34,7 +38,10 @@ impl Analyzer for Connection<Pool<MariaDB>> {
         .fetch_all(&self.pool)
         .await?;
 
-        let rows = rows.into_iter().map(|row| TableName::new(row.0)).collect();
+        let rows = rows
+            .into_iter()
+            .map(|(name, table_type)| (TableName::new(name), table_kind(&table_type)))
+            .collect();
 
         Ok(rows)
     }
@@ -43,8 +50,23 @@ impl Analyzer for Connection<Pool<MariaDB>> {
         &self,
         database: &SchemaObjectName,
     ) -> Result<HashMap<TableName, Vec<Column>>, Error> {
-        let rows: Vec<(String, String)> = sqlx::query_as(
-            "SELECT TABLE_NAME, COLUMN_NAME\n\
+        #[derive(sqlx::FromRow)]
+        struct ColumnRow {
+            table_name: String,
+            column_name: String,
+            column_type: String,
+            is_nullable: String,
+            column_default: Option<String>,
+            extra: String,
+        }
+
+        let rows: Vec<ColumnRow> = sqlx::query_as(
+            "SELECT TABLE_NAME AS table_name,\n\
+                    COLUMN_NAME AS column_name,\n\
+                    COLUMN_TYPE AS column_type,\n\
+                    IS_NULLABLE AS is_nullable,\n\
+                    COLUMN_DEFAULT AS column_default,\n\
+                    EXTRA AS extra\n\
              FROM information_schema.COLUMNS\n\
              WHERE TABLE_SCHEMA = ?\n\
              ORDER BY ORDINAL_POSITION\n\
@@ -55,7 +77,16 @@ impl Analyzer for Connection<Pool<MariaDB>> {
         .await?;
 
         let mut columns = HashMap::new();
-        for (table_name, column_name) in rows {
+        for row in rows {
+            let ColumnRow {
+                table_name,
+                column_name,
+                column_type,
+                is_nullable,
+                column_default,
+                extra,
+            } = row;
+
             let table_name = TableName::new(table_name);
             if !columns.contains_key(&table_name) {
                 columns.insert(table_name.clone(), Vec::new());
@@ -66,6 +97,12 @@ impl Analyzer for Connection<Pool<MariaDB>> {
                 .expect("We made sure there's a val here right above.");
             table_columns.push(Column {
                 name: ColumnName(column_name),
+                data_type: column_type,
+                nullable: is_nullable == "YES",
+                // MariaDB reports "no default" as a literal NULL. String defaults are quoted, so
+                // a 'NULL' default string would not be confused with this.
+                default: column_default.filter(|default| default != "NULL"),
+                auto_increment: extra.to_lowercase().contains("auto_increment"),
             });
         }
 
@@ -95,7 +132,7 @@ impl Analyzer for Connection<Pool<MariaDB>> {
                 constraint_name,
                 table_name,
                 column_name,
-                _referenced_db_name,
+                referenced_db_name,
                 referenced_table_name,
                 referenced_column_name,
             ) = row;
@@ -106,13 +143,18 @@ impl Analyzer for Connection<Pool<MariaDB>> {
                 .entry(constraint_name)
                 .or_insert_with(|| ForeignKey {
                     from: KeyReference {
+                        database: None,
                         table: table_name,
                         key: Key { columns: vec![] },
                     },
                     to: KeyReference {
+                        // Keys to other databases are rare, but they do exist.
+                        database: Some(DatabaseName(referenced_db_name))
+                            .filter(|referenced_db| referenced_db.0 != database.as_str()),
                         table: TableName::new(referenced_table_name),
                         key: Key { columns: vec![] },
                     },
+                    kind: ForeignKeyKind::Declared,
                 });
 
             fk.from.key.columns.push(ColumnName(column_name));
@@ -134,7 +176,7 @@ impl Analyzer for Connection<Pool<MariaDB>> {
         let rows: Vec<(String, String)> = sqlx::query_as(
             "SELECT TABLE_NAME, COLUMN_NAME\n\
             FROM information_schema.KEY_COLUMN_USAGE\n\
-            WHERE REFERENCED_TABLE_NAME is null\n\
+            WHERE CONSTRAINT_NAME = 'PRIMARY'\n\
                 AND TABLE_SCHEMA = ?\n\
 	        ORDER BY TABLE_NAME, ORDINAL_POSITION\n\
             LIMIT 25000",
@@ -155,4 +197,47 @@ impl Analyzer for Connection<Pool<MariaDB>> {
 
         Ok(pks)
     }
+
+    async fn table_indexes(
+        &self,
+        database: &SchemaObjectName,
+    ) -> Result<HashMap<TableName, Vec<Index>>, Error> {
+        let rows: Vec<(String, String, i64, String)> = sqlx::query_as(
+            "SELECT TABLE_NAME, INDEX_NAME, NON_UNIQUE, COLUMN_NAME\n\
+            FROM information_schema.STATISTICS\n\
+            WHERE TABLE_SCHEMA = ?\n\
+            ORDER BY TABLE_NAME, INDEX_NAME, SEQ_IN_INDEX\n\
+            LIMIT 25000",
+        )
+        .bind(database.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut indexes: HashMap<TableName, Vec<Index>> = HashMap::new();
+        for (table, index_name, non_unique, column) in rows {
+            let table_indexes = indexes.entry(TableName::new(table)).or_default();
+
+            // Rows are ordered by index, so all columns of an index come one after another.
+            match table_indexes.last_mut() {
+                Some(index) if index.name == index_name => {
+                    index.columns.push(ColumnName(column));
+                }
+                _ => table_indexes.push(Index {
+                    name: index_name,
+                    columns: vec![ColumnName(column)],
+                    unique: non_unique == 0,
+                }),
+            }
+        }
+
+        Ok(indexes)
+    }
+}
+
+fn table_kind(table_type: &str) -> TableKind {
+    match table_type {
+        // MariaDB also has "SYSTEM VIEW"s for information_schema & co.
+        "VIEW" | "SYSTEM VIEW" => TableKind::View,
+        _ => TableKind::Table,
+    }
 }
@@ -1,6 +1,7 @@
 use super::Connection;
 use crate::analyze::{
-    Column, ColumnName, ForeignKey, Key, KeyReference, SchemaObjectName, TableName,
+    Column, ColumnName, DatabaseName, ForeignKey, ForeignKeyKind, Index, Key, KeyReference,
+    SchemaObjectName, TableKind, TableName,
 };
 use crate::engine::sql::querying::to_id;
 use crate::engine::sql::querying::Analyzer;
@@ -25,9 +26,12 @@ impl Analyzer for Connection<Pool<Postgres>> {
         Ok(rows)
     }
 
-    async fn list_tables(&self, database: &SchemaObjectName) -> Result<Vec<TableName>, Error> {
-        let rows: Vec<(String,)> = sqlx::query_as(
-            "SELECT table_name\n\
+    async fn list_tables(
+        &self,
+        database: &SchemaObjectName,
+    ) -> Result<Vec<(TableName, TableKind)>, Error> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT table_name, table_type\n\
              FROM information_schema.tables\n\
              WHERE TABLE_SCHEMA = $1",
         )
@@ -37,7 +41,14 @@ impl Analyzer for Connection<Pool<Postgres>> {
 
         let rows = rows
             .into_iter()
-            .map(|(name,)| TableName::new(name))
+            .map(|(name, table_type)| {
+                let kind = match table_type.as_str() {
+                    "VIEW" => TableKind::View,
+                    _ => TableKind::Table,
+                };
+
+                (TableName::new(name), kind)
+            })
             .collect();
 
         Ok(rows)
@@ -50,8 +61,27 @@ impl Analyzer for Connection<Pool<Postgres>> {
         // it's called "database" for MySQL, but we use this field for schema
         let schema = database;
 
-        let rows: Vec<(String, String)> = sqlx::query_as(
-            "SELECT table_name, column_name\n\
+        #[derive(sqlx::FromRow)]
+        struct ColumnRow {
+            table_name: String,
+            column_name: String,
+            data_type: String,
+            is_nullable: String,
+            column_default: Option<String>,
+            is_identity: String,
+        }
+
+        let rows: Vec<ColumnRow> = sqlx::query_as(
+            "SELECT table_name::text AS table_name,\n\
+                    column_name::text AS column_name,\n\
+                    CASE\n\
+                        WHEN character_maximum_length IS NOT NULL\n\
+                            THEN data_type || '(' || character_maximum_length || ')'\n\
+                        ELSE data_type\n\
+                    END::text AS data_type,\n\
+                    is_nullable::text AS is_nullable,\n\
+                    column_default::text AS column_default,\n\
+                    is_identity::text AS is_identity\n\
              FROM information_schema.columns\n\
              WHERE TABLE_SCHEMA = $1\n\
              ORDER BY ordinal_position\n\
@@ -62,7 +92,21 @@ impl Analyzer for Connection<Pool<Postgres>> {
         .await?;
 
         let mut columns = HashMap::new();
-        for (table_name, column_name) in rows {
+        for row in rows {
+            let ColumnRow {
+                table_name,
+                column_name,
+                data_type,
+                is_nullable,
+                column_default,
+                is_identity,
+            } = row;
+
+            // Serial columns are just columns with a sequence as a default value.
+            let is_serial = column_default
+                .as_ref()
+                .is_some_and(|default| default.starts_with("nextval("));
+
             let table_name = TableName::new(table_name);
             if !columns.contains_key(&table_name) {
                 columns.insert(table_name.clone(), Vec::new());
@@ -73,6 +117,10 @@ impl Analyzer for Connection<Pool<Postgres>> {
                 .expect("We made sure there's a val here right above.");
             table_columns.push(Column {
                 name: ColumnName(column_name),
+                data_type,
+                nullable: is_nullable == "YES",
+                default: column_default,
+                auto_increment: is_identity == "YES" || is_serial,
             });
         }
 
@@ -93,6 +141,7 @@ impl Analyzer for Connection<Pool<Postgres>> {
             ct_name: String,
             table_name: String,
             column_name: String,
+            foreign_schema: String,
             foreign_table_name: String,
             foreign_column_name: String,
         }
@@ -104,6 +153,7 @@ impl Analyzer for Connection<Pool<Postgres>> {
                     kcu.constraint_name AS ct_name,\n\
                     t.table_name AS table_name,\n\
                     kcu.column_name as column_name,\n\
+                    ccu.table_schema AS foreign_schema,\n\
                     ccu.table_name AS foreign_table_name,\n\
                     ccu.column_name AS foreign_column_name\n\
                 FROM information_schema.table_constraints AS tc\n\
@@ -141,6 +191,7 @@ impl Analyzer for Connection<Pool<Postgres>> {
                 ct_name,
                 table_name,
                 column_name,
+                foreign_schema,
                 foreign_table_name,
                 foreign_column_name,
             } = row;
@@ -153,13 +204,17 @@ impl Analyzer for Connection<Pool<Postgres>> {
                 .entry((ct_catalog, ct_schema, ct_name))
                 .or_insert_with(|| ForeignKey {
                     from: KeyReference {
+                        database: None,
                         table: table_name,
                         key: Key { columns: vec![] },
                     },
                     to: KeyReference {
+                        database: Some(DatabaseName(foreign_schema))
+                            .filter(|foreign_schema| foreign_schema.0 != schema.as_str()),
                         table: TableName::new(foreign_table_name),
                         key: Key { columns: vec![] },
                     },
+                    kind: ForeignKeyKind::Declared,
                 });
 
             fk.from.key.columns.push(ColumnName(column_name));
@@ -196,7 +251,7 @@ impl Analyzer for Connection<Pool<Postgres>> {
                 WHERE tab.table_catalog = $1
                     AND tab.table_schema = $2
                     AND column_name is not null
-                    -- AND tab.table_type = 'BASE TABLE'
+                    AND tab.table_type = 'BASE TABLE'
                 ORDER BY tab.table_schema, tab.table_name
                 LIMIT 25000",
         )
@@ -217,6 +272,80 @@ impl Analyzer for Connection<Pool<Postgres>> {
 
         Ok(pks)
     }
+
+    async fn table_indexes(
+        &self,
+        database: &SchemaObjectName,
+    ) -> Result<HashMap<TableName, Vec<Index>>, Error> {
+        // it's called "database" for MySQL, but we use this field for schema
+        let schema = database;
+
+        let rows: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT tablename::text, indexname::text, indexdef\n\
+             FROM pg_indexes\n\
+             WHERE schemaname = $1\n\
+             ORDER BY tablename, indexname\n\
+             LIMIT 25000",
+        )
+        .bind(schema.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut indexes: HashMap<TableName, Vec<Index>> = HashMap::new();
+        for (table, index_name, index_definition) in rows {
+            indexes
+                .entry(TableName::new(table))
+                .or_default()
+                .push(Index {
+                    name: index_name,
+                    columns: index_columns(&index_definition),
+                    unique: index_definition.starts_with("CREATE UNIQUE INDEX"),
+                });
+        }
+
+        Ok(indexes)
+    }
+}
+
+/// pg_indexes only gives us the index definition, which looks like this:
+/// ```sql
+/// CREATE UNIQUE INDEX users_pkey ON public.users USING btree (id, "createdAt" DESC)
+/// ```
+///
+/// Expression indexes (`lower(email)`) will result in "columns" that don't exist, which is fine,
+/// since they can't be used when filtering on just the column anyway.
+fn index_columns(index_definition: &str) -> Vec<ColumnName> {
+    let column_list = index_definition
+        .split_once(" USING ")
+        .and_then(|(_, method_and_columns)| method_and_columns.split_once('('))
+        .map(|(_, columns)| columns)
+        .unwrap_or_default();
+
+    let mut columns = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+
+    for char in column_list.chars() {
+        match char {
+            '(' => depth += 1,
+            ')' if depth == 0 => break,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                columns.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+
+        current.push(char);
+    }
+    columns.push(current);
+
+    columns
+        .iter()
+        .filter_map(|column| column.split_whitespace().next())
+        .map(|column| ColumnName(column.trim_matches('"').to_string()))
+        .collect()
 }
 
 fn require_database(options: &PgConnectOptions) -> Result<&str, InternalError> {
@@ -225,3 +354,30 @@ fn require_database(options: &PgConnectOptions) -> Result<&str, InternalError> {
         host = &options.get_host()
     )))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::index_columns;
+    use crate::analyze::ColumnName;
+
+    #[test]
+    fn test_index_columns() {
+        let columns = index_columns(
+            "CREATE UNIQUE INDEX users_pkey ON public.users USING btree (id, \"createdAt\" DESC)",
+        );
+
+        assert_eq!(
+            columns,
+            vec![ColumnName::from("id"), ColumnName::from("createdAt")]
+        );
+    }
+
+    #[test]
+    fn test_expression_index_columns() {
+        let columns = index_columns(
+            "CREATE INDEX users_email ON public.users USING btree (lower((email)::text)) WHERE (id > 3)",
+        );
+
+        assert_eq!(columns, vec![ColumnName::from("lower((email)::text)")]);
+    }
+}
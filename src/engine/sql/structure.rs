@@ -2,10 +2,13 @@
 //! augment our Pines.
 use crate::analyze::SchemaObjectName;
 use crate::cache::CacheableMap;
+use crate::engine::sql::graph::ForeignKeyGraph;
 use crate::engine::syntax::SqlIdentifierInput;
+use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::time::SystemTime;
 
 /// Each server config will be cached to disk to responding to queries way snappier.
 ///
@@ -13,7 +16,22 @@ use std::fmt::{Display, Formatter};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Server {
     pub params: ServerParams,
-    pub databases: HashMap<DatabaseName, Database>,
+    /// Private, so nothing can change the foreign keys without resetting the graph.
+    databases: HashMap<DatabaseName, Database>,
+    /// Caches written before we kept track of this don't have it.
+    #[serde(default)]
+    pub analyzed_at: Option<SystemTime>,
+    /// Whether `pine analyze --infer-foreign-keys` was used, so a refresh can do the same.
+    #[serde(default)]
+    pub infers_foreign_keys: bool,
+    /// Databases the cache has, but we didn't need to read. We still want to suggest them when
+    /// the user misspells a database.
+    #[serde(skip)]
+    pub unloaded_databases: Vec<DatabaseName>,
+    /// Built the first time we need it. Anything that changes the foreign keys after that has to
+    /// reset it.
+    #[serde(skip)]
+    graph: OnceCell<ForeignKeyGraph>,
 }
 
 /// Parameters used to connect to a server
@@ -44,29 +62,85 @@ pub struct Database {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Column {
     pub name: ColumnName,
+    /// The column type, as reported by the server. For example: "varchar(256)".
+    #[serde(default)]
+    pub data_type: String,
+    /// Analyses from before this was recorded don't know, and assuming NOT NULL would be wrong.
+    #[serde(default = "nullable_by_default")]
+    pub nullable: bool,
+    /// The default value expression, as reported by the server. Strings will be quoted.
+    #[serde(default)]
+    pub default: Option<String>,
+    /// Covers both MariaDB's AUTO_INCREMENT and Postgres' identity and serial columns.
+    #[serde(default)]
+    pub auto_increment: bool,
+}
+
+fn nullable_by_default() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Table {
     pub name: TableName,
-    pub primary_key: Key,
+    #[serde(default)]
+    pub kind: TableKind,
+    /// Views, log tables and the like don't always have primary keys.
+    pub primary_key: Option<Key>,
     pub columns: Vec<Column>,
     pub foreign_keys: Vec<ForeignKey>,
+    /// All indexes, including the one backing the primary key.
+    #[serde(default)]
+    pub indexes: Vec<Index>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// We treat views just like tables, but it's nice to know which is which when something does not
+/// work as expected. Views, for example, never have primary keys.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TableKind {
+    #[default]
+    Table,
+    View,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct Key {
     pub columns: Vec<ColumnName>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Index {
+    pub name: String,
+    /// The order of the columns matters: only the leading column(s) can be used on their own.
+    pub columns: Vec<ColumnName>,
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct ForeignKey {
     pub from: KeyReference,
     pub to: KeyReference,
+    #[serde(default)]
+    pub kind: ForeignKeyKind,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Hash)]
+pub enum ForeignKeyKind {
+    /// The database knows about this foreign key.
+    #[default]
+    Declared,
+    /// We guessed this foreign key based on column names, see `pine analyze --infer-foreign-keys`.
+    Inferred,
+    /// The user told us about this foreign key, see `pine relation add`.
+    Virtual,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct KeyReference {
+    /// Only set when the key points to a different database (MariaDB) or schema (Postgres) than
+    /// the one of the table the foreign key belongs to.
+    #[serde(default)]
+    pub database: Option<DatabaseName>,
     pub table: TableName,
     pub key: Key,
 }
@@ -80,6 +154,39 @@ pub struct TableName(String);
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DatabaseName(pub String);
 
+impl Server {
+    pub fn new(params: ServerParams, databases: HashMap<DatabaseName, Database>) -> Server {
+        Server {
+            params,
+            databases,
+            analyzed_at: None,
+            infers_foreign_keys: false,
+            unloaded_databases: vec![],
+            graph: OnceCell::new(),
+        }
+    }
+
+    pub fn foreign_key_graph(&self) -> &ForeignKeyGraph {
+        self.graph
+            .get_or_init(|| ForeignKeyGraph::new(&self.databases))
+    }
+
+    pub fn databases(&self) -> &HashMap<DatabaseName, Database> {
+        &self.databases
+    }
+
+    /// The foreign key graph is rebuilt the next time it's needed, in case the keys change.
+    pub fn databases_mut(&mut self) -> &mut HashMap<DatabaseName, Database> {
+        self.graph = OnceCell::new();
+
+        &mut self.databases
+    }
+
+    pub fn into_databases(self) -> HashMap<DatabaseName, Database> {
+        self.databases
+    }
+}
+
 impl TableName {
     /// Creates a TableName without a schema. Useful for MariaDB.
     pub fn new(name: String) -> Self {
@@ -97,6 +204,28 @@ impl Table {
             .iter()
             .find(|foreign_key| foreign_key.to.table == to_table)
     }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    pub fn is_indexed(&self, column: &str) -> bool {
+        self.indexes.iter().any(|index| index.covers(column))
+    }
+}
+
+impl Index {
+    /// A column is covered by an index if it's the leading column of that index. Otherwise,
+    /// filtering or ordering by that column alone will not be able to use the index.
+    pub fn covers(&self, column: &str) -> bool {
+        self.columns.first().is_some_and(|first| first == &column)
+    }
+}
+
+impl KeyReference {
+    pub fn database_or<'a>(&'a self, owner_database: &'a DatabaseName) -> &'a DatabaseName {
+        self.database.as_ref().unwrap_or(owner_database)
+    }
 }
 
 impl ForeignKey {
@@ -113,6 +242,7 @@ impl ForeignKey {
         ForeignKey {
             from: self.to.clone(),
             to: self.from.clone(),
+            kind: self.kind,
         }
     }
 }
@@ -135,6 +265,15 @@ impl PartialEq<SqlIdentifierInput<'_>> for TableName {
     }
 }
 
+impl Display for TableKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableKind::Table => write!(f, "table"),
+            TableKind::View => write!(f, "view"),
+        }
+    }
+}
+
 impl Display for TableName {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -163,6 +302,10 @@ impl<T: Into<String>> From<T> for Column {
     fn from(name: T) -> Column {
         Column {
             name: name.into().into(),
+            data_type: String::new(),
+            nullable: true,
+            default: None,
+            auto_increment: false,
         }
     }
 }
@@ -173,6 +316,16 @@ impl<'a> From<&'a TableName> for &'a str {
     }
 }
 
+impl ServerParams {
+    /// The database (MariaDB) or schema (Postgres) used when the user does not specify one.
+    pub fn default_database(&self) -> Option<&DatabaseName> {
+        match self.db_type {
+            DBType::PostgresSQL => self.default_schema.as_ref(),
+            DBType::MariaDB => Some(&self.database),
+        }
+    }
+}
+
 impl Display for ServerParams {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         if self.port == 3306 {
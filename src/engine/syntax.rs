@@ -41,7 +41,7 @@ use crate::engine::syntax::stage2::Stage2Rep;
 use crate::engine::syntax::stage3::Stage3Rep;
 use crate::engine::Sourced;
 
-pub fn parse_to_stage4(input: &str) -> Result<Stage4Rep, crate::error::Error> {
+pub fn parse_to_stage4(input: &str) -> Result<Stage4Rep<'_>, crate::error::Error> {
     let stage1 = parse_stage1(input)?;
     let stage2: Stage2Rep = stage1.into();
     let stage3: Stage3Rep = stage2.into();
@@ -1,4 +1,5 @@
 //! The stage 1 representation is just the raw output from Pest
+use pest::error::ErrorVariant;
 use pest::iterators::Pairs;
 use pest::Parser;
 use pest_derive::Parser;
@@ -8,8 +9,10 @@ use thiserror::Error;
 ///
 /// Our syntax is constructed so once it's parsed, it will *always* be valid.
 /// Stage 1 can fail, but in terms of parsing to a Pine, nothing else can.
-/// This means this stage is the only one that can have an error.
+/// This means this stage is the only one that can have an error, so the few checks the grammar
+/// can't express are done here as well.
 #[derive(Error, Debug)]
+#[allow(dead_code)]
 pub enum Stage1Error {
     // Pest has quite nice error output, which rely on. See the test::test_error test for an example.
     /// Invalid syntax error
@@ -32,9 +35,36 @@ pub fn parse_stage1(input: &str) -> Result<Stage1Rep<'_>, crate::error::Error> {
         input,
     )?; // "?" automatically transforms Pest errors into Stage1Errors into crate::error:Errors
 
+    check_neighbors_depth(&pest)?;
+
     Ok(Stage1Rep { pest })
 }
 
+/// `|3` has to be a depth we can actually walk, `|0` would be an empty tree.
+fn check_neighbors_depth(pest: &Pairs<Rule>) -> Result<(), pest::error::Error<Rule>> {
+    let depths = pest
+        .clone()
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::neighbors_depth && pair.as_str() != "|");
+
+    for depth in depths {
+        let message = match depth.as_str().parse::<usize>() {
+            Ok(0) => "the depth has to be at least 1, use `|` for the direct neighbors",
+            Ok(_) => continue,
+            Err(_) => "the depth is too large",
+        };
+
+        return Err(pest::error::Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: message.to_string(),
+            },
+            depth.as_span(),
+        ));
+    }
+
+    Ok(())
+}
+
 /// Pest pair holder
 ///
 /// Up until a later stage, all of the data can be directly found in the input string.
@@ -72,4 +102,17 @@ mod tests {
             "
         );
     }
+
+    #[test]
+    fn test_neighbors_depth() {
+        assert!(parse_stage1("users |3").is_ok());
+
+        let error = parse_stage1("users |0").unwrap_err().to_string();
+        assert!(error.contains("the depth has to be at least 1"), "{error}");
+
+        let error = parse_stage1("users |99999999999999999999")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("the depth is too large"), "{error}");
+    }
 }
@@ -83,14 +83,24 @@ pub enum Stage2Pine<'a> {
     ExplicitAutoJoin(Sourced<Stage2ExplicitAutoJoin<'a>>),
     /// We'll figure out the table, this acts as a join: + where:.
     CompoundJoin(Sourced<Stage2CompoundJoin<'a>>),
-    /// Show all the tables I can directly join from the previous table.
-    ShowNeighbors(Source),
+    /// Show all the tables I can directly join from the previous table. With a depth, show
+    /// the tables reachable in that many joins as a tree.
+    ShowNeighbors(Source, Option<usize>),
     /// Show all the columns from the last table.
     ShowColumns(Source),
+    /// Show all the indexes from the last table.
+    ShowIndexes(Source),
+    /// Show the tables of the default database, only the ones containing the filter if there is
+    /// one. Like the base, this can only be the first pine.
+    ShowTables(Option<&'a str>),
+    /// Show all the databases. Can only be the first pine.
+    ShowDatabases,
 }
 
 pub type Stage2Selectable<'a> = SelectableHolder<Stage2Condition<'a>, Computation<'a>>;
 
+// These only live while a pine is translated, boxing them isn't worth it.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum Stage2Condition<'a> {
     ImplicitId(Sourced<Stage2LiteralValue<'a>>),
@@ -145,7 +155,7 @@ pub struct PestIterator<'a> {
 }
 
 impl<'a> PestIterator<'a> {
-    fn new(base: Pairs<'a, Rule>) -> PestIterator {
+    fn new(base: Pairs<'a, Rule>) -> PestIterator<'a> {
         Self {
             base_done: false,
             inners: base,
@@ -169,8 +179,13 @@ impl<'a> Iterator for PestIterator<'a> {
         // By having this if here, we simplify our translate_pine function
         if !self.base_done {
             self.base_done = true;
+            let first = next.expect("Guaranteed by syntax");
 
-            return Some(translate_base(next.expect("Guaranteed by syntax")));
+            // Listings don't need a table, so they take the place of the base.
+            return match first.as_rule() {
+                Rule::base => Some(translate_base(first)),
+                _ => translate_pine(first),
+            };
         }
 
         match next {
@@ -234,6 +249,9 @@ fn translate_pine(pair: Pair<Rule>) -> Option<Sourced<Stage2Pine>> {
         Rule::unselect_pine => translate_unselect_pine(pair),
         Rule::show_neighbors_pine => translate_show_neighbors_pine(pair),
         Rule::show_columns_pine => show_columns_pine(pair),
+        Rule::show_indexes_pine => show_indexes_pine(pair),
+        Rule::show_tables_pine => show_tables_pine(pair),
+        Rule::show_databases_pine => Stage2Pine::ShowDatabases,
         Rule::EOI => return None, // EOI is End Of Input
         _ => panic!("Unknown pine {:#?}", pair),
     };
@@ -401,7 +419,13 @@ fn translate_unselect_pine(unselect: Pair<Rule>) -> Stage2Pine {
 fn translate_show_neighbors_pine(pine: Pair<Rule>) -> Stage2Pine<'static> {
     assert_eq!(Rule::show_neighbors_pine, pine.as_rule());
 
-    Stage2Pine::ShowNeighbors(Source::Input(pine.as_span().into()))
+    let source = Source::Input(pine.as_span().into());
+    let depth = pine.into_inner().next().map(|depth| match depth.as_str() {
+        "|" => 2,
+        digits => digits.parse().expect("Checked in stage 1"),
+    });
+
+    Stage2Pine::ShowNeighbors(source, depth)
 }
 
 fn show_columns_pine(pine: Pair<Rule>) -> Stage2Pine<'static> {
@@ -410,6 +434,20 @@ fn show_columns_pine(pine: Pair<Rule>) -> Stage2Pine<'static> {
     Stage2Pine::ShowColumns(Source::Input(pine.as_span().into()))
 }
 
+fn show_indexes_pine(pine: Pair<Rule>) -> Stage2Pine<'static> {
+    assert_eq!(Rule::show_indexes_pine, pine.as_rule());
+
+    Stage2Pine::ShowIndexes(Source::Input(pine.as_span().into()))
+}
+
+fn show_tables_pine(pine: Pair<Rule>) -> Stage2Pine {
+    assert_eq!(Rule::show_tables_pine, pine.as_rule());
+
+    let filter = pine.into_inner().next().map(|filter| filter.as_str());
+
+    Stage2Pine::ShowTables(filter)
+}
+
 fn translate_order(order: Pair<Rule>) -> Sourced<Stage2Order> {
     assert_eq!(Rule::order, order.as_rule());
 
@@ -36,8 +36,11 @@ pub enum Stage3Pine<'a> {
     Order(Vec<Sourced<Stage3Order<'a>>>),
     GroupBy(Vec<Sourced<Stage3Selectable<'a>>>),
     Limit(Sourced<Stage3Limit<'a>>),
-    ShowNeighbors(Sourced<TableInput<'a>>),
+    ShowNeighbors(Sourced<TableInput<'a>>, Option<usize>),
     ShowColumns(Sourced<TableInput<'a>>),
+    ShowIndexes(Sourced<TableInput<'a>>),
+    ShowTables(Option<&'a str>),
+    ShowDatabases,
 }
 
 // shh! keep these secret
@@ -92,4 +95,14 @@ mod test {
             }
         ))
     }
+
+    #[test]
+    fn test_listing_convert() {
+        let stage2: Stage2Rep = parse_stage1("t? pref").unwrap().into();
+        let mut stage3: Stage3Rep = stage2.into();
+
+        let first = stage3.pines.next().unwrap();
+        assert!(matches!(first.it, Stage3Pine::ShowTables(Some("pref"))));
+        assert!(stage3.pines.next().is_none());
+    }
 }
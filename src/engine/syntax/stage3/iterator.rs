@@ -11,7 +11,7 @@ use crate::engine::syntax::stage3::{
 };
 use crate::engine::syntax::stage4::Stage4FunctionCall;
 use crate::engine::syntax::{
-    ColumnInput, Computation, FunctionCall, Stage2LiteralValue, TableInput,
+    ColumnInput, Computation, FunctionCall, OptionalInput, Stage2LiteralValue, TableInput,
 };
 use crate::engine::{JoinConditions, Source, Sourced};
 use std::collections::VecDeque;
@@ -24,7 +24,9 @@ pub struct Stage3Iterator<'a> {
     stage3_buffer: Stage3Buffer<'a>,
     /// Unlike previous steps, we will need to flesh out stage 3 pines some contextual data
     /// derived from the processing of previous pines.
-    context: Context<'a>,
+    ///
+    /// Listings have no table, but nothing can follow them either, so there's no context to keep.
+    context: Option<Context<'a>>,
 }
 
 /// Type aliases make our code cleaner.
@@ -75,6 +77,12 @@ impl<'a> Stage3Iterator<'a> {
 
         let (base_table, conditions) = match base.it {
             Stage2Pine::Base { table, conditions } => (table, conditions),
+            Stage2Pine::ShowTables(filter) => {
+                return Self::listing(stage2_pines, source, Stage3Pine::ShowTables(filter))
+            }
+            Stage2Pine::ShowDatabases => {
+                return Self::listing(stage2_pines, source, Stage3Pine::ShowDatabases)
+            }
             // Same as above, the grammar should guarantee this panic! never happens.
             _ => panic!("Unknown starting pine, expected base"),
         };
@@ -93,12 +101,27 @@ impl<'a> Stage3Iterator<'a> {
                     conditions,
                 },
             )]),
-            context: Context {
+            context: Some(Context {
                 previous_table: base_table,
-            },
+            }),
+        }
+    }
+
+    fn listing(stage2_pines: PestIterator<'a>, source: Source, listing: Stage3Pine<'a>) -> Self {
+        Self {
+            stage2_source: stage2_pines,
+            stage3_buffer: VecDeque::from([Sourced::from_source(source, listing)]),
+            context: None,
         }
     }
 
+    fn previous_table(&self) -> Sourced<TableInput<'a>> {
+        self.context
+            .as_ref()
+            .expect("The grammar doesn't allow pines after listings")
+            .previous_table
+    }
+
     /// Tries to generate religious victory points for the stage 3 buffer by cleansing filthy heretics
     /// from the stage 2 source.
     fn consume_from_stage2(&mut self) {
@@ -115,7 +138,9 @@ impl<'a> Stage3Iterator<'a> {
         let position = stage2_pine.source;
 
         let stage3_pines = match stage2_pine.it {
-            Stage2Pine::Base { .. } => panic!("This was covered in the constructor"),
+            Stage2Pine::Base { .. } | Stage2Pine::ShowTables(_) | Stage2Pine::ShowDatabases => {
+                panic!("This was covered in the constructor")
+            }
             Stage2Pine::Select(columns) => self.translate_select(position, columns),
             Stage2Pine::Filter(conditions) => self.process_filter_conditions(position, conditions),
             Stage2Pine::Limit(limit) => self.process_limit(position, limit),
@@ -129,8 +154,9 @@ impl<'a> Stage3Iterator<'a> {
                 self.process_explicit_auto_join(position, auto_join)
             }
             Stage2Pine::CompoundJoin(auto_join) => self.process_auto_join(position, auto_join),
-            Stage2Pine::ShowNeighbors(source) => self.process_show_neighbors(source),
+            Stage2Pine::ShowNeighbors(source, depth) => self.process_show_neighbors(source, depth),
             Stage2Pine::ShowColumns(source) => self.process_show_columns(source),
+            Stage2Pine::ShowIndexes(source) => self.process_show_indexes(source),
         };
 
         stage3_pines
@@ -143,7 +169,7 @@ impl<'a> Stage3Iterator<'a> {
     ) -> Stage3Buffer<'a> {
         let columns = columns
             .iter()
-            .map(|column| translate_selectable(column, &self.context.previous_table))
+            .map(|column| translate_selectable(column, &self.previous_table()))
             .collect();
 
         VecDeque::from([Sourced::from_source(source, Stage3Pine::Select(columns))])
@@ -164,7 +190,7 @@ impl<'a> Stage3Iterator<'a> {
     ) -> Stage3Buffer<'a> {
         let orders = orders
             .into_iter()
-            .map(|order| translate_order(order, &self.context.previous_table))
+            .map(|order| translate_order(order, &self.previous_table()))
             .collect();
 
         VecDeque::from([Sourced::from_source(source, Stage3Pine::Order(orders))])
@@ -177,7 +203,7 @@ impl<'a> Stage3Iterator<'a> {
     ) -> Stage3Buffer<'a> {
         let selectables = groups
             .iter()
-            .map(|column| translate_selectable(column, &self.context.previous_table))
+            .map(|column| translate_selectable(column, &self.previous_table()))
             .collect();
 
         VecDeque::from([Sourced::from_source(
@@ -193,7 +219,7 @@ impl<'a> Stage3Iterator<'a> {
     ) -> Stage3Buffer<'a> {
         let columns = columns
             .iter()
-            .map(|column| translate_column_input(column, &self.context.previous_table))
+            .map(|column| translate_column_input(column, &self.previous_table()))
             .collect();
 
         VecDeque::from([Sourced::from_source(source, Stage3Pine::Unselect(columns))])
@@ -207,11 +233,7 @@ impl<'a> Stage3Iterator<'a> {
         let conditions = conditions
             .iter()
             .map(|condition| {
-                translate_condition(
-                    condition,
-                    &self.context.previous_table,
-                    &self.context.previous_table,
-                )
+                translate_condition(condition, &self.previous_table(), &self.previous_table())
             })
             .collect();
 
@@ -223,7 +245,7 @@ impl<'a> Stage3Iterator<'a> {
         source: Source,
         join: Sourced<Stage2Join<'a>>,
     ) -> Stage3Buffer<'a> {
-        let left_implicit_table = &self.context.previous_table;
+        let left_implicit_table = &self.previous_table();
         let right_implicit_table = &join.it.target_table;
 
         let conditions = match join.it.conditions {
@@ -253,7 +275,7 @@ impl<'a> Stage3Iterator<'a> {
                 join.source,
                 Stage3Join {
                     join_type,
-                    source_table: self.context.previous_table,
+                    source_table: self.previous_table(),
                     target_table,
                     conditions,
                 },
@@ -261,7 +283,9 @@ impl<'a> Stage3Iterator<'a> {
         );
 
         // Future pines will implicitly reference this table
-        self.context.previous_table = target_table;
+        self.context = Some(Context {
+            previous_table: target_table,
+        });
 
         VecDeque::from([stage3_join])
     }
@@ -282,7 +306,7 @@ impl<'a> Stage3Iterator<'a> {
                 join.source,
                 Stage3Join {
                     join_type,
-                    source_table: self.context.previous_table,
+                    source_table: self.previous_table(),
                     target_table,
                     conditions: JoinConditions::Auto,
                 },
@@ -290,7 +314,9 @@ impl<'a> Stage3Iterator<'a> {
         );
 
         // Future pines will implicitly reference this table
-        self.context.previous_table = target_table;
+        self.context = Some(Context {
+            previous_table: target_table,
+        });
 
         VecDeque::from([stage3_join])
     }
@@ -310,17 +336,24 @@ impl<'a> Stage3Iterator<'a> {
         result
     }
 
-    fn process_show_neighbors(&self, source: Source) -> Stage3Buffer<'a> {
+    fn process_show_neighbors(&self, source: Source, depth: Option<usize>) -> Stage3Buffer<'a> {
         VecDeque::from([Sourced::from_source(
             source,
-            Stage3Pine::ShowNeighbors(self.context.previous_table),
+            Stage3Pine::ShowNeighbors(self.previous_table(), depth),
         )])
     }
 
     fn process_show_columns(&self, source: Source) -> Stage3Buffer<'a> {
         VecDeque::from([Sourced::from_source(
             source,
-            Stage3Pine::ShowColumns(self.context.previous_table),
+            Stage3Pine::ShowColumns(self.previous_table()),
+        )])
+    }
+
+    fn process_show_indexes(&self, source: Source) -> Stage3Buffer<'a> {
+        VecDeque::from([Sourced::from_source(
+            source,
+            Stage3Pine::ShowIndexes(self.previous_table()),
         )])
     }
 }
@@ -429,6 +462,7 @@ fn translate_column_input<'a>(
     column.map_ref(|column| Stage3ColumnInput {
         column: column.column,
         table: column.table.or(*implicit_table),
+        table_is_implicit: matches!(column.table, OptionalInput::Implicit),
     })
 }
 
@@ -13,10 +13,16 @@ use crate::engine::{
 };
 use crate::engine::{LiteralValueHolder, Sourced};
 
+#[allow(clippy::large_enum_variant)]
 pub enum Stage4Rep<'a> {
     Query(Stage4Query<'a>),
-    ShowNeighbors(Sourced<TableInput<'a>>),
+    /// Without a depth, only the direct neighbors are listed.
+    ShowNeighbors(Sourced<TableInput<'a>>, Option<usize>),
     ShowColumns(Sourced<TableInput<'a>>),
+    ShowIndexes(Sourced<TableInput<'a>>),
+    /// Tables of the default database, only the ones containing the filter if there is one.
+    ShowTables(Option<&'a str>),
+    ShowDatabases,
 }
 
 pub struct Stage4Query<'a> {
@@ -32,6 +38,7 @@ pub struct Stage4Query<'a> {
 
 pub type Stage4Selectable<'a> = SelectableHolder<Stage4Condition<'a>, Stage4ComputationInput<'a>>;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum Stage4Condition<'a> {
     ImplicitId(Sourced<TableInput<'a>>, Sourced<Stage4LiteralValue<'a>>),
@@ -59,6 +66,9 @@ pub type Stage4JoinCondition<'a> = JoinConditions<Stage4Condition<'a>>;
 pub struct Stage4ColumnInput<'a> {
     pub table: Sourced<TableInput<'a>>, // we always know it because of SYNTAX
     pub column: Sourced<SqlIdentifierInput<'a>>,
+    /// The user didn't write the table, so it's just the previous table. The column might still
+    /// belong to another table of the pine.
+    pub table_is_implicit: bool,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -137,12 +147,17 @@ impl<'a> From<Stage3Rep<'a>> for Stage4Rep<'a> {
                     // For example "table1 | s: id | j: table2" will select "table1.id, table2.*"
                     add_implicit_select = true;
                 }
-                Stage3Pine::ShowNeighbors(for_table) => {
-                    return Stage4Rep::ShowNeighbors(for_table.into());
+                Stage3Pine::ShowNeighbors(for_table, depth) => {
+                    return Stage4Rep::ShowNeighbors(for_table.into(), depth);
                 }
                 Stage3Pine::ShowColumns(for_table) => {
                     return Stage4Rep::ShowColumns(for_table.into());
                 }
+                Stage3Pine::ShowIndexes(for_table) => {
+                    return Stage4Rep::ShowIndexes(for_table.into());
+                }
+                Stage3Pine::ShowTables(filter) => return Stage4Rep::ShowTables(filter),
+                Stage3Pine::ShowDatabases => return Stage4Rep::ShowDatabases,
             }
         }
 
@@ -174,6 +189,7 @@ fn implicit_wildcard_select(last_table: Sourced<TableInput>) -> Sourced<Stage4Se
         Stage4ComputationInput::Column(Sourced::implicit(Stage4ColumnInput {
             table: last_table,
             column: Sourced::implicit(SqlIdentifierInput { name: "*" }),
+            table_is_implicit: false,
         })),
     )))
 }
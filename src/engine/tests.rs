@@ -88,7 +88,7 @@ fn run_single_test(test: &Test, server: &Server) -> Outcome {
     let mut found_output = found_output.unwrap();
 
     // I'm adding this here just so we can keep the integration test .sql files 100% valid SQL.
-    found_output.push_str(";");
+    found_output.push(';');
 
     if test.expected() == found_output {
         Outcome::Success
@@ -135,7 +135,7 @@ impl TestResult {
     /// # let report: TestErrorReport;
     /// println!("{report}"); // like I said, convenient
     /// ```
-    pub fn to_error_report(&self) -> Option<TestErrorReport> {
+    pub fn to_error_report(&self) -> Option<TestErrorReport<'_>> {
         match &self.outcome {
             Outcome::Success => None,
             Outcome::Error(err) => match err {
@@ -162,7 +162,7 @@ impl TestResult {
         }
     }
 
-    fn test_header_line(&self, test_outcome: TestOutcome) -> TestHeaderLine {
+    fn test_header_line(&self, test_outcome: TestOutcome) -> TestHeaderLine<'_> {
         TestHeaderLine {
             module: self.test.file.split_once('/').expect("We know it's fine").1,
             input: self.test.input(),
@@ -170,7 +170,7 @@ impl TestResult {
         }
     }
 
-    fn test_highlighting_file_extract(&self) -> FileExtract {
+    fn test_highlighting_file_extract(&self) -> FileExtract<'_> {
         let gutter_width = (self.test.line_nr + self.test.expected().lines().count())
             .to_string()
             .len()
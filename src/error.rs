@@ -1,6 +1,7 @@
 mod pest;
 mod rendering;
 
+use crate::cache::IncompatibleCacheError;
 use crate::engine::sql::DbStructureParseError;
 use crate::engine::RenderingError;
 use crate::error::pest::WrappedPestError;
@@ -44,6 +45,8 @@ pub enum ErrorKind {
     IoError(#[from] std::io::Error),
     #[error("{}\n{0}", "JSON error".bold())]
     JsonError(#[from] serde_json::Error),
+    #[error("{}\n{0}", "Outdated cache".bold())]
+    IncompatibleCacheError(#[from] IncompatibleCacheError),
     #[error("{}\n{0}", "Error reading data from stdin".bold())]
     DialogueError(#[from] dialoguer::Error),
 }
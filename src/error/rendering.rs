@@ -44,11 +44,17 @@ impl QueryBuildError {
         let sources = match self {
             QueryBuildError::InvalidPostgresConfig
             | QueryBuildError::DefaultDatabaseNotFound(_) => return vec![], // It's not found in the input
-            QueryBuildError::DatabaseNotFound(db) => vec![db.source],
-            QueryBuildError::TableNotFound(table) => vec![table.source],
+            QueryBuildError::DatabaseNotFound(db, _) => vec![db.source],
+            QueryBuildError::TableNotFound(table, _) => vec![table.source],
+            // The table is often implicit, and far from the column.
+            QueryBuildError::ColumnNotFound(_, column, _)
+            | QueryBuildError::AmbiguousColumn(_, column, _)
+            | QueryBuildError::ColumnInSeveralTables(column, _) => vec![column.source],
+            QueryBuildError::AmbiguousTable(table, _) => vec![table.source],
             QueryBuildError::InvalidForeignKey { from, to } => vec![from.source, to.source],
             QueryBuildError::JoinNotFound { from, to } => vec![from.source, to.source],
-            QueryBuildError::InvalidImplicitIdCondition(table, _, value) => {
+            QueryBuildError::InvalidImplicitIdCondition(table, _, value)
+            | QueryBuildError::MissingPrimaryKey(table, _, value) => {
                 vec![table.source, value.source]
             }
         };
@@ -69,16 +75,40 @@ impl QueryBuildError {
         positions
     }
 
-    fn message(&self) -> &str {
-        match self {
+    fn message(&self) -> String {
+        let message = match self {
             QueryBuildError::InvalidPostgresConfig => "Postgres context is misconfigured",
             QueryBuildError::DefaultDatabaseNotFound(_) => "Default database not found",
-            QueryBuildError::DatabaseNotFound(_) => "Database not found",
-            QueryBuildError::TableNotFound(_) => "Table not found",
+            QueryBuildError::DatabaseNotFound(_, suggestions) => {
+                return with_suggestions("Database not found", suggestions)
+            }
+            QueryBuildError::TableNotFound(_, suggestions) => {
+                return with_suggestions("Table not found", suggestions)
+            }
+            QueryBuildError::ColumnNotFound(_, _, suggestions) => {
+                return with_suggestions("Column not found", suggestions)
+            }
+            QueryBuildError::AmbiguousTable(..) => "Ambiguous table abbreviation",
+            QueryBuildError::AmbiguousColumn(..) => "Ambiguous column abbreviation",
+            QueryBuildError::ColumnInSeveralTables(..) => "Column found in several tables",
             QueryBuildError::InvalidForeignKey { .. } => "Invalid foreign key between tables",
             QueryBuildError::JoinNotFound { .. } => "Can't join tables",
             QueryBuildError::InvalidImplicitIdCondition(..) => "Can't use implicit id filtering",
-        }
+            QueryBuildError::MissingPrimaryKey(..) => "Can't use implicit id filtering",
+        };
+
+        message.to_string()
+    }
+}
+
+/// "Table not found, did you mean `users` or `userPreferences`?"
+fn with_suggestions(message: &str, suggestions: &[String]) -> String {
+    let quoted: Vec<_> = suggestions.iter().map(|name| format!("`{name}`")).collect();
+
+    match quoted.split_last() {
+        None => message.to_string(),
+        Some((only, [])) => format!("{message}, did you mean {only}?"),
+        Some((last, rest)) => format!("{message}, did you mean {} or {last}?", rest.join(", ")),
     }
 }
 
@@ -100,7 +130,7 @@ impl Display for QueryBuildError {
                 default_db = format!("{}", server.database).yellow().bold(),
                 switch_context = "pine use-context <context name>".green().bold(),
             ),
-            QueryBuildError::DatabaseNotFound(database) => write!(
+            QueryBuildError::DatabaseNotFound(database, _) => write!(
                 f,
                 "The {database} database is not present in your context. \n\
                  If your context is out of date, re-run {pine_analyze}. \n\
@@ -109,7 +139,7 @@ impl Display for QueryBuildError {
                 pine_analyze = "pine analyze".green().bold(),
                 switch_context = "pine use-context <context name>".green().bold(),
             ),
-            QueryBuildError::TableNotFound(table) => write!(
+            QueryBuildError::TableNotFound(table, _) => write!(
                 f,
                 "The {table} table is not present in your context. \n\
                  If your context is out of date, re-run {pine_analyze}. \n\
@@ -118,6 +148,36 @@ impl Display for QueryBuildError {
                 pine_analyze = "pine analyze".green().bold(),
                 switch_context = "pine use-context <context name>".green().bold(),
             ),
+            QueryBuildError::ColumnNotFound(table, column, _) => write!(
+                f,
+                "The {table} table has no {column} column. \n\
+                 If your context is out of date, re-run {pine_analyze}.",
+                table = format!("{}", table).yellow().bold(),
+                column = format!("{}", column).yellow().bold(),
+                pine_analyze = "pine analyze".green().bold(),
+            ),
+            QueryBuildError::AmbiguousTable(table, candidates) => write!(
+                f,
+                "{table} could be any of these tables: {candidates}.\n\
+                 Type more of the name to pick one.",
+                table = format!("{}", table).yellow().bold(),
+                candidates = candidates.join(", ").yellow().bold(),
+            ),
+            QueryBuildError::AmbiguousColumn(table, column, candidates) => write!(
+                f,
+                "{column} could be any of these columns of the {table} table: {candidates}.\n\
+                 Type more of the name to pick one.",
+                table = format!("{}", table).yellow().bold(),
+                column = format!("{}", column).yellow().bold(),
+                candidates = candidates.join(", ").yellow().bold(),
+            ),
+            QueryBuildError::ColumnInSeveralTables(column, tables) => write!(
+                f,
+                "{column} could come from any of these tables: {tables}.\n\
+                 Prefix it with the table name.",
+                column = format!("{}", column).yellow().bold(),
+                tables = tables.join(", ").yellow().bold(),
+            ),
             QueryBuildError::InvalidForeignKey { from, to } => write!(
                 f,
                 "The foreign key linking {from} to {to} is not usable.\n\
@@ -146,6 +206,16 @@ impl Display for QueryBuildError {
                     .collect::<Vec<_>>().join(", ").yellow().bold(),
                 pine_analyze = "pine analyze".green().bold(),
             ),
+            QueryBuildError::MissingPrimaryKey(table, kind, value) => write!(
+                f,
+                "Cannot use implicit id conditions for the {table} {kind} because it has no primary key.\n\
+                 Filter on a column instead, for example {example}.\n\
+                 \n\
+                 If your context is out of date, re-run {pine_analyze}.",
+                table = format!("{}", table).yellow().bold(),
+                example = format!("{table} | w: id = {value}").green().bold(),
+                pine_analyze = "pine analyze".green().bold(),
+            ),
         }
     }
 }
@@ -3,12 +3,18 @@
 
 pub mod cache;
 pub mod context;
+pub mod credentials;
 mod engine;
 mod error;
+pub mod project;
+pub mod relations;
 
-pub use engine::render;
+pub use engine::{referenced_databases, render, render_with, RenderOptions};
 
 pub mod analyze {
+    pub use crate::engine::sql::diff::{diff_databases, SchemaChange};
+    pub use crate::engine::sql::graph::ForeignKeyGraph;
+    pub use crate::engine::sql::inference::infer_foreign_keys;
     pub use crate::engine::sql::querying::{
         mariadb, postgres, Analyzer, Connection, MariaDBConnection, SchemaObjectName,
     };
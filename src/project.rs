@@ -0,0 +1,190 @@
+//! A `.pine.json` file in a project (or any of its parent directories) picks the context to use
+//! there, so each checkout translates against the right database without `pine use-context`.
+//!
+//! ```json
+//! {
+//!   "context": "shop-local",
+//!   "limit": 50,
+//!   "max_cache_age_days": 30,
+//!   "relations": ["orders.customerId -> users.id"],
+//!   "aliases": { "u": "users", "paid": "orders status = 'paid'" }
+//! }
+//! ```
+use crate::context::ContextName;
+use crate::engine::RenderOptions;
+use crate::relations::{Relation, Relations};
+use crate::InternalError;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+pub const PROJECT_FILE: &str = ".pine.json";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Project {
+    /// Where we found the settings, so we can mention it in errors.
+    #[serde(skip)]
+    pub file: PathBuf,
+    pub context: Option<ContextName>,
+    /// Replaces the default limit of 10 rows.
+    pub limit: Option<usize>,
+    /// Written like `pine relation add` arguments: `orders.customerId -> users.id`.
+    #[serde(default)]
+    pub relations: Vec<String>,
+    /// `pine translate` warns when the analyzed structure is older than this.
+    pub max_cache_age_days: Option<u64>,
+    /// Pines starting with an alias get it replaced: `u 1` becomes `users 1`.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+
+static CURRENT_PROJECT: OnceCell<Option<Project>> = OnceCell::new();
+
+impl Project {
+    /// The project the current directory belongs to, if any. Looked up only once.
+    pub fn current() -> Result<Option<&'static Project>, crate::Error> {
+        CURRENT_PROJECT
+            .get_or_try_init(|| Self::discover(&env::current_dir()?))
+            .map(Option::as_ref)
+    }
+
+    /// Walks up from `directory` until it finds a project file.
+    pub fn discover(directory: &Path) -> Result<Option<Project>, crate::Error> {
+        for directory in directory.ancestors() {
+            let file = directory.join(PROJECT_FILE);
+
+            if file.is_file() {
+                return Self::read(file).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn read(file: PathBuf) -> Result<Project, crate::Error> {
+        let content = fs::read_to_string(&file)?;
+        let project: Project = serde_json::from_str(&content).map_err(|error| {
+            InternalError(format!("Invalid project file {}: {error}", file.display()))
+        })?;
+
+        Ok(Project { file, ..project })
+    }
+
+    pub fn relations(&self, context: &ContextName) -> Result<Relations, crate::Error> {
+        let relations = self
+            .relations
+            .iter()
+            .map(|relation| relation.parse::<Relation>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Relations {
+            context: context.clone(),
+            relations,
+        })
+    }
+
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            default_limit: self.limit,
+        }
+    }
+
+    /// Only the first word of each pine is replaced, so columns and values with the same name
+    /// as an alias are left alone.
+    pub fn expand_aliases(&self, input: &str) -> String {
+        split_pines(input)
+            .into_iter()
+            .map(|pine| {
+                let start = pine.len() - pine.trim_start().len();
+                let end = pine[start..]
+                    .find(char::is_whitespace)
+                    .map_or(pine.len(), |end| start + end);
+
+                match self.aliases.get(&pine[start..end]) {
+                    Some(expansion) => format!("{}{expansion}{}", &pine[..start], &pine[end..]),
+                    None => pine.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("|")
+    }
+}
+
+/// Splits on `|`, except inside quoted values.
+fn split_pines(input: &str) -> Vec<&str> {
+    let mut pines = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+
+    for (position, char) in input.char_indices() {
+        match (quote, char) {
+            (None, '\'' | '"') => quote = Some(char),
+            (Some(open), _) if open == char => quote = None,
+            (None, '|') => {
+                pines.push(&input[start..position]);
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    pines.push(&input[start..]);
+
+    pines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(aliases: &[(&str, &str)]) -> Project {
+        Project {
+            aliases: aliases
+                .iter()
+                .map(|(alias, expansion)| (alias.to_string(), expansion.to_string()))
+                .collect(),
+            ..Project::default()
+        }
+    }
+
+    #[test]
+    fn test_expand_aliases() {
+        let project = project(&[("u", "users"), ("paid", "orders status = 'paid'")]);
+
+        assert_eq!(
+            project.expand_aliases("u 1 | paid"),
+            "users 1 | orders status = 'paid'"
+        );
+        assert_eq!(project.expand_aliases("users | s: u"), "users | s: u");
+        assert_eq!(
+            project.expand_aliases("users name = 'a | u' | u"),
+            "users name = 'a | u' | users"
+        );
+    }
+
+    #[test]
+    fn test_discover_in_parent_directory() {
+        // Separate test runs can happen at the same time.
+        let root = env::temp_dir().join(format!("rusty-pine-test-project-{}", std::process::id()));
+        let nested = root.join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            root.join(PROJECT_FILE),
+            r#"{"context": "shop", "limit": 50, "relations": ["orders.userId -> users.id"]}"#,
+        )
+        .unwrap();
+
+        let project = Project::discover(&nested).unwrap().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(project.file, root.join(PROJECT_FILE));
+        assert_eq!(project.context, Some("shop".into()));
+        assert_eq!(project.limit, Some(50));
+        assert_eq!(
+            project.relations(&"shop".into()).unwrap().relations.len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Relations are foreign keys the database does not know about: polymorphic links, links between
+//! databases, or just keys nobody bothered to declare.
+//!
+//! They're saved per context, and merged into the analyzed structure each time we read it. This
+//! way they survive re-running `pine analyze`.
+use crate::analyze::{
+    ColumnName, DatabaseName, ForeignKey, ForeignKeyKind, Key, KeyReference, Server, TableName,
+};
+use crate::cache;
+use crate::context::ContextName;
+use crate::error::ErrorKind;
+use crate::InternalError;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::io::ErrorKind as IoErrorKind;
+use std::str::FromStr;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Relations {
+    pub context: ContextName,
+    pub relations: Vec<Relation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Relation {
+    pub from: RelationEnd,
+    pub to: RelationEnd,
+}
+
+/// One side of a relation, written as `[database.]table.column[+column...]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelationEnd {
+    /// Falls back to the default database (MariaDB) or schema (Postgres) of the context.
+    pub database: Option<DatabaseName>,
+    pub table: TableName,
+    pub columns: Vec<ColumnName>,
+}
+
+impl Relations {
+    /// Contexts without any relations don't have a file, that's fine.
+    pub fn read(context: &ContextName) -> Result<Relations, crate::Error> {
+        match cache::read(context) {
+            Ok(relations) => Ok(relations),
+            Err(error) => match error.into_inner() {
+                ErrorKind::IoError(io_error) if io_error.kind() == IoErrorKind::NotFound => {
+                    Ok(Relations {
+                        context: context.clone(),
+                        relations: vec![],
+                    })
+                }
+                error => Err(error.into()),
+            },
+        }
+    }
+}
+
+impl Relation {
+    pub fn new(from: RelationEnd, to: RelationEnd) -> Result<Relation, crate::Error> {
+        if from.columns.len() != to.columns.len() {
+            Err(InternalError(format!(
+                "Both sides of a relation need the same number of columns: {from} has {}, {to} has {}",
+                from.columns.len(),
+                to.columns.len(),
+            )))?;
+        }
+
+        Ok(Relation { from, to })
+    }
+
+    /// Foreign keys only mention the database they point to if it's not their own.
+    fn as_foreign_key(
+        &self,
+        from_database: &DatabaseName,
+        to_database: &DatabaseName,
+    ) -> ForeignKey {
+        ForeignKey {
+            from: self.from.as_key_reference(None),
+            to: self
+                .to
+                .as_key_reference(Some(to_database).filter(|&db| db != from_database)),
+            kind: ForeignKeyKind::Virtual,
+        }
+    }
+}
+
+impl RelationEnd {
+    fn as_key_reference(&self, database: Option<&DatabaseName>) -> KeyReference {
+        KeyReference {
+            database: database.cloned(),
+            table: self.table.clone(),
+            key: Key {
+                columns: self.columns.clone(),
+            },
+        }
+    }
+}
+
+impl Server {
+    /// Relations to tables we don't know about are skipped: the database might have changed since
+    /// the relation was added.
+    pub fn add_relations(&mut self, relations: &Relations) {
+        let default_database = self.params.default_database().cloned();
+
+        for relation in &relations.relations {
+            let from_database = relation
+                .from
+                .database
+                .as_ref()
+                .or(default_database.as_ref());
+            let to_database = relation.to.database.as_ref().or(default_database.as_ref());
+
+            let (Some(from_database), Some(to_database)) = (from_database, to_database) else {
+                warn!("skipping relation {relation}: no default database or schema");
+                continue;
+            };
+
+            let table = self
+                .databases_mut()
+                .get_mut(from_database)
+                .and_then(|database| database.tables.get_mut(&relation.from.table));
+
+            match table {
+                Some(table) => {
+                    let foreign_key = relation.as_foreign_key(from_database, to_database);
+
+                    if !table.foreign_keys.contains(&foreign_key) {
+                        table.foreign_keys.push(foreign_key);
+                    }
+                }
+                None => warn!("skipping relation {relation}: table not found"),
+            }
+        }
+    }
+}
+
+impl FromStr for RelationEnd {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = input.split('.').collect();
+
+        let (database, table, columns) = match parts.as_slice() {
+            [table, columns] => (None, table, columns),
+            [database, table, columns] => {
+                (Some(DatabaseName(database.to_string())), table, columns)
+            }
+            _ => Err(InternalError(format!(
+                "Expected [database.]table.column[+column...], found \"{input}\""
+            )))?,
+        };
+
+        let columns: Vec<ColumnName> = columns.split('+').map(ColumnName::from).collect();
+        if table.is_empty() || columns.iter().any(|column| column.0.is_empty()) {
+            Err(InternalError(format!(
+                "Table and column names can't be empty: \"{input}\""
+            )))?;
+        }
+
+        Ok(RelationEnd {
+            database,
+            table: TableName::from(*table),
+            columns,
+        })
+    }
+}
+
+/// The same format we display relations in: `from -> to`.
+impl FromStr for Relation {
+    type Err = crate::Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let Some((from, to)) = input.split_once("->") else {
+            Err(InternalError(format!(
+                "Expected a relation like \"orders.userId -> users.id\", found \"{input}\""
+            )))?
+        };
+
+        Relation::new(from.trim().parse()?, to.trim().parse()?)
+    }
+}
+
+impl Display for Relation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} -> {}", self.from, self.to)
+    }
+}
+
+impl Display for RelationEnd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(database) = &self.database {
+            write!(f, "{}.", database.0)?;
+        }
+
+        let columns: Vec<_> = self.columns.iter().map(|c| c.0.as_str()).collect();
+
+        write!(f, "{}.{}", self.table, columns.join("+"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::sql::fixtures::{server, table};
+
+    #[test]
+    fn test_parse_relation_end() {
+        let end: RelationEnd = "billing.invoices.userId+teamId".parse().unwrap();
+
+        assert_eq!(end.database, Some(DatabaseName("billing".to_string())));
+        assert_eq!(end.table, "invoices");
+        assert_eq!(end.columns.len(), 2);
+        assert_eq!(end.columns[0], "userId");
+        assert_eq!(end.columns[1], "teamId");
+        assert_eq!(end.to_string(), "billing.invoices.userId+teamId");
+    }
+
+    #[test]
+    fn test_parse_invalid_relation_end() {
+        assert!("users".parse::<RelationEnd>().is_err());
+        assert!("users.".parse::<RelationEnd>().is_err());
+        assert!("users.id+".parse::<RelationEnd>().is_err());
+    }
+
+    #[test]
+    fn test_relations_are_used_for_joins() {
+        let mut server = server(vec![(
+            "default",
+            vec![table("users", &["id"]), table("orders", &["userId"])],
+        )]);
+        let relation = Relation::new(
+            "orders.userId".parse().unwrap(),
+            "users.id".parse().unwrap(),
+        )
+        .unwrap();
+
+        server.add_relations(&Relations {
+            context: "test".into(),
+            relations: vec![relation],
+        });
+        let query = crate::render("users | orders", &server).unwrap();
+
+        assert!(query.contains("LEFT JOIN users ON users.id = orders.userId"));
+    }
+
+    #[test]
+    fn test_relation_needs_matching_columns() {
+        let from = "orders.userId+teamId".parse().unwrap();
+        let to = "users.id".parse().unwrap();
+
+        assert!(Relation::new(from, to).is_err());
+    }
+}
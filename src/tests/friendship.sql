@@ -123,6 +123,7 @@ WHERE preferences.id = 1 AND preferences.personId = 3
 LIMIT 10;
 
 -- Test: friendMap | friendshipProperties tag="test"
+-- Warning: friendshipProperties.tag is not indexed, filtering or ordering by it might be slow
 SELECT friendshipProperties.*
 FROM friendshipProperties
 LEFT JOIN friendMap ON friendMap.friendA = friendshipProperties.friendA AND friendMap.friendB = friendshipProperties.friendB
@@ -130,6 +131,8 @@ WHERE friendshipProperties.tag = "test"
 LIMIT 10;
 
 -- Test: people | o: id- name+ dateOfBirth
+-- Warning: people.name is not indexed, filtering or ordering by it might be slow
+-- Warning: people.dateOfBirth is not indexed, filtering or ordering by it might be slow
 SELECT *
 FROM people
 ORDER BY id DESC, name, dateOfBirth DESC
@@ -189,3 +192,10 @@ SELECT *
 FROM preferences
 WHERE id = 1
 LIMIT 10;
+
+-- Test: preferences | i?
+/*
+Indexes for `preferences`:
+  PRIMARY   (id) unique
+  personId  (personId)
+*/--;
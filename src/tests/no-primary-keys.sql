@@ -0,0 +1,30 @@
+-- Tables without primary keys, like log tables, can be used like any other table.
+-- Only the implicit id shorthand (`logs 1`) does not work with them.
+create table `people` (
+    `id`   int auto_increment,
+    `name` varchar(256) null,
+    primary key (`id`),
+)
+    charset = utf8mb4;
+
+create table `peopleLog` (
+    `personId` int          not null,
+    `message`  varchar(256) not null,
+    constraint `peopleLog_people_fk`
+        foreign key (`personId`) references `people` (`id`)
+)
+    charset = utf8mb4;
+
+-- Test: people 1 | peopleLog
+SELECT peopleLog.*
+FROM peopleLog
+LEFT JOIN people ON people.id = peopleLog.personId
+WHERE people.id = 1
+LIMIT 10;
+
+-- Test: peopleLog personId=1 | people
+SELECT people.*
+FROM people
+LEFT JOIN peopleLog ON peopleLog.personId = people.id
+WHERE peopleLog.personId = 1
+LIMIT 10;
@@ -71,17 +71,34 @@ FROM preferences
 LEFT JOIN people ON people.id = preferences.personId
 LIMIT 10;
 
--- Test: people | s: id isBlue
-SELECT id, isBlue
+-- Test: people | s: id name
+SELECT id, name
 FROM people
 LIMIT 10;
 
+-- Test: people | preferences | s: name value
+SELECT people.name, preferences.value
+FROM preferences
+LEFT JOIN people ON people.id = preferences.personId
+LIMIT 10;
+
 -- Test: people | preferences
 SELECT preferences.*
 FROM preferences
 LEFT JOIN people ON people.id = preferences.personId
 LIMIT 10;
 
+-- Test: pe | fM
+SELECT friendMap.*
+FROM friendMap
+LEFT JOIN people ON people.id = friendMap.friendA
+LIMIT 10;
+
+-- Test: fL | s: frA meta
+SELECT friendA, metadata
+FROM friendshipLog
+LIMIT 10;
+
 -- Test: people | preferences | friendshipLog
 SELECT friendshipLog.*
 FROM friendshipLog
@@ -131,6 +148,24 @@ Foreign keys to:
   people.id using .friendB
 */--;
 
+-- Test: preferences ||
+/*
+Neighbors of `preferences`, up to 2 joins away:
+  outgoing preferences.personId -> people.id                 preferences | people
+    incoming friendMap.friendA -> people.id                  preferences | people | friendMap
+    incoming friendMap.friendB -> people.id                  preferences | people | friendMap
+    incoming friendshipLog.friendA -> people.id              preferences | people | friendshipLog
+    incoming friendshipLog.friendB -> people.id              preferences | people | friendshipLog
+  incoming preferenceHistory.preferenceId -> preferences.id  preferences | preferenceHistory
+*/--;
+
+-- Test: preferences |1
+/*
+Neighbors of `preferences`, up to 1 joins away:
+  outgoing preferences.personId -> people.id                 preferences | people
+  incoming preferenceHistory.preferenceId -> preferences.id  preferences | preferenceHistory
+*/--;
+
 -- Test: people | s: count(1)
 SELECT count(1)
 FROM people
@@ -139,8 +174,32 @@ LIMIT 10;
 -- Test: people | c?
 /*
 Columns for `people`:
-  id
-  name
-  dateOfBirth
-  placeOfBirth
+  id            int           not null auto_increment
+  name          varchar(256)  null
+  dateOfBirth   date          not null
+  placeOfBirth  varchar(256)  not null
+*/--;
+
+-- Test: t?
+/*
+Tables in `default`:
+  friendMap
+  friendshipLog
+  friendshipProperties
+  people
+  preferenceHistory
+  preferences
+*/--;
+
+-- Test: t? PREF
+/*
+Tables in `default` matching `PREF`:
+  preferenceHistory
+  preferences
+*/--;
+
+-- Test: db?
+/*
+Databases:
+  default (default)
 */--;